@@ -1,64 +1,134 @@
-use anyhow::Result;
-use headless_chrome::{types::PrintToPdfOptions, Browser, LaunchOptions};
-use pdfium_render::prelude::*;
+use readah::readability::helper::pdf_to_text;
+use readah::readability::{
+    BrowserSession, ConfigCollection, ConsentAction, ConsentHandler, Device, ExpandOptions,
+    RenderOptions, SessionOptions, Wait, WaitCondition,
+};
 use std::env;
+use std::path::PathBuf;
+use std::time::Duration;
+use url::Url;
+
+const USAGE: &str = "usage: headless_cli [--device desktop|ipad|iphone|android] [--viewport WxH] \
+[--user-agent UA] [--scale S] [--paper WxH] [--pages RANGES] [--timeout SECS] [--background] \
+[--wait-selector CSS] [--wait-xpath XPATH] [--wait-network-idle MS] [--wait-delay MS] \
+[--wait-text-stable MS] [--wait-timeout SECS] [--config DIR] [--consent accept|reject|remove] [--scroll] [--max-scrolls N] [--expand CSS] URL";
 
 // this code add commandline function on top of the headless example
 // cargo run --example headless_cli --release https://web.site.tovisit
+// cargo run --example headless_cli --release -- --device iphone --pages 1-2 https://web.site.tovisit
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
-    let args: Vec<String> = env::args().collect();
-    let url = args.into_iter().nth(1).unwrap();
-
-    let options = LaunchOptions {
-        headless: true,
-        window_size: Some((820, 1180)),
-        ..Default::default()
-    };
-
-    let browser = Browser::new(options)?;
-
-    let tab = browser.new_tab()?;
-
-    // tab.set_default_timeout(Duration::from_secs(3));
-    tab.navigate_to(&url)?;
-    tab.wait_until_navigated()?;
-
-    let pdf_options: Option<PrintToPdfOptions> = Some(PrintToPdfOptions {
-        landscape: Some(false),
-        display_header_footer: Some(false),
-        print_background: Some(false),
-        scale: Some(0.5),
-        paper_width: Some(11.0),
-        paper_height: Some(17.0),
-        margin_top: Some(0.1),
-        margin_bottom: Some(0.1),
-        margin_left: Some(0.1),
-        margin_right: Some(0.1),
+    let mut args = env::args().skip(1);
+    let mut options = RenderOptions {
         page_ranges: Some("1-2".to_string()),
-        ignore_invalid_page_ranges: Some(true),
-        prefer_css_page_size: Some(false),
-        transfer_mode: None,
         ..Default::default()
-    });
-
-    let pdf_data = tab.print_to_pdf(pdf_options)?;
+    };
+    let mut url = None;
+    let mut device = None;
+    let mut viewport = None;
+    let mut user_agent = None;
+    let mut wait = Vec::new();
+    let mut wait_timeout = Duration::from_secs(10);
+    let mut config_dir = None;
+    let mut scroll = false;
+    let mut max_scrolls = None;
+    let mut expand = Vec::new();
 
-    let pdf_as_vec = pdf_data.to_vec();
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .ok_or_else(|| anyhow::anyhow!("{arg} needs a value"))
+        };
+        match arg.as_str() {
+            "--device" => {
+                let name = value()?;
+                device = Some(
+                    Device::parse(&name).ok_or_else(|| anyhow::anyhow!("unknown device {name}"))?,
+                );
+            }
+            "--viewport" => viewport = Some(parse_size::<u32>(&value()?)?),
+            "--user-agent" => user_agent = Some(value()?),
+            "--scale" => options.scale = value()?.parse()?,
+            "--paper" => options.paper_size = parse_size(&value()?)?,
+            "--pages" => options.page_ranges = Some(value()?),
+            "--timeout" => options.timeout = Duration::from_secs(value()?.parse()?),
+            "--background" => options.print_background = true,
+            "--wait-selector" => wait.push(WaitCondition::Selector(value()?)),
+            "--wait-xpath" => wait.push(WaitCondition::XPath(value()?)),
+            "--wait-network-idle" => {
+                wait.push(WaitCondition::NetworkIdle(parse_millis(&value()?)?))
+            }
+            "--wait-delay" => wait.push(WaitCondition::Delay(parse_millis(&value()?)?)),
+            "--wait-text-stable" => wait.push(WaitCondition::TextStable(parse_millis(&value()?)?)),
+            "--wait-timeout" => wait_timeout = Duration::from_secs(value()?.parse()?),
+            "--config" => config_dir = Some(PathBuf::from(value()?)),
+            "--consent" => {
+                let action = match value()?.as_str() {
+                    "accept" => ConsentAction::Accept,
+                    "reject" => ConsentAction::Reject,
+                    "remove" => ConsentAction::Remove,
+                    action => anyhow::bail!("unknown consent action {action}"),
+                };
+                options.consent = Some(ConsentHandler::new(action));
+            }
+            "--scroll" => scroll = true,
+            "--max-scrolls" => max_scrolls = Some(value()?.parse()?),
+            "--expand" => expand.push(value()?),
+            _ if arg.starts_with("--") => anyhow::bail!("unknown option {arg}\n{USAGE}"),
+            _ => url = Some(arg),
+        }
+    }
+    let url = url.ok_or_else(|| anyhow::anyhow!(USAGE))?;
+    // the device only sets the defaults, explicit flags win in any order
+    if let Some(device) = device {
+        options.viewport = device.viewport();
+        options.user_agent = Some(device.user_agent().to_owned());
+    }
+    if let Some((width, height)) = viewport {
+        options.viewport.width = width;
+        options.viewport.height = height;
+    }
+    if user_agent.is_some() {
+        options.user_agent = user_agent;
+    }
+    options.wait = wait
+        .into_iter()
+        .map(|condition| Wait::new(condition, wait_timeout))
+        .collect();
+    // site configs name the content a page has to render, e.g. ftr-site-config
+    if let Some(config_dir) = config_dir {
+        let configs = ConfigCollection::parse_directory(&config_dir).await?;
+        options.wait_for_config(&configs, &Url::parse(&url)?, wait_timeout);
+    }
+    if scroll || !expand.is_empty() {
+        let defaults = ExpandOptions::default();
+        options.expand = Some(ExpandOptions {
+            scroll,
+            max_scrolls: max_scrolls.unwrap_or(defaults.max_scrolls),
+            selectors: if expand.is_empty() {
+                defaults.selectors.clone()
+            } else {
+                expand
+            },
+            ..defaults
+        });
+    }
 
-    let text = Pdfium::new(
-        Pdfium::bind_to_library(Pdfium::pdfium_platform_library_name_at_path(
-            "/Users/jaykchen/Downloads/pdfium-mac-arm64/lib/libpdfium.dylib",
-        ))
-        .or_else(|_| Pdfium::bind_to_system_library())?,
-    )
-    .load_pdf_from_byte_vec(pdf_as_vec, Some(""))?
-    .pages()
-    .iter()
-    .map(|page| page.text().unwrap().all())
-    .collect::<Vec<String>>()
-    .join(" ");
+    let session = BrowserSession::new(SessionOptions::default())?;
+    let pdf_data = session.pdf(&url, &options)?;
+    let text = pdf_to_text(pdf_data)?;
 
     println!("{:?}", text);
     Ok(())
 }
+
+// "820x1180" as (820, 1180)
+fn parse_size<T: std::str::FromStr>(size: &str) -> anyhow::Result<(T, T)> {
+    size.split_once('x')
+        .and_then(|(width, height)| Some((width.parse().ok()?, height.parse().ok()?)))
+        .ok_or_else(|| anyhow::anyhow!("invalid size {size}, expected WIDTHxHEIGHT"))
+}
+
+fn parse_millis(millis: &str) -> anyhow::Result<Duration> {
+    Ok(Duration::from_millis(millis.parse()?))
+}
@@ -1,7 +1,7 @@
 use anyhow::Result;
 use html2text::from_read;
 use http_req::request;
-use readah::readability::Readability;
+use readah::readability::{ExtractOptions, Readability};
 use url::Url;
 
 // use Readability to clean the dom of the html fetched by http_req
@@ -16,10 +16,17 @@ async fn main() -> Result<()> {
     let base_url = Url::parse(&format!("{}://{}", scheme, host)).unwrap();
 
     let mut writer = Vec::new(); //container for body of a response
-    let res = request::get(url, &mut writer).unwrap();
-    match Readability::extract(&String::from_utf8(writer).unwrap(), Some(base_url)).await {
+    let response = request::get(url, &mut writer).unwrap();
+    let content_type = response.headers().get("Content-Type");
+    match Readability::extract_from_bytes(
+        &writer,
+        content_type.map(|s| s.as_str()),
+        Some(base_url),
+        &ExtractOptions::default(),
+    )
+    .await
+    {
         Ok(res) => {
-
             // use html2text to extract text content from the simplied html dom
             let output = from_read(res.to_string().as_bytes(), 80);
 
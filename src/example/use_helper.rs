@@ -1,12 +1,19 @@
 use readah::readability::helper::*;
+use readah::readability::{BrowserSession, RenderOptions, SessionOptions};
 use std::env;
+use std::sync::Arc;
 #[tokio::main]
-async fn main() {
+async fn main() -> anyhow::Result<()> {
     // let url = "https://example.com/";
-    let args: Vec<String> = env::args().collect();
-    let url = args.into_iter().nth(1).unwrap();
+    // all urls given on the commandline share one browser
+    let urls: Vec<String> = env::args().skip(1).collect();
+    let session = Arc::new(BrowserSession::new(SessionOptions::default())?);
 
-    if let Ok(text) = text_to_use(&url).await {
-        println!("{:?}", text);
+    for url in urls {
+        if let Ok(text) = text_to_use_with_session(&session, &url, &RenderOptions::default()).await
+        {
+            println!("{:?}", text);
+        }
     }
+    Ok(())
 }
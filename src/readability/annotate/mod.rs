@@ -0,0 +1,316 @@
+use std::collections::HashMap;
+
+use libxml::tree::{Document, Node, SaveOptions};
+
+use super::constants::NODE_ID_ATTR;
+use super::trace::{Trace, TraceAction, TraceCandidate};
+
+const STYLE: &str = r#"
+.readability-legend { position: sticky; top: 0; z-index: 1000; padding: 6px 10px; background: #222; color: #eee; font: 13px/1.6 sans-serif; }
+.readability-legend span { margin-right: 14px; padding: 1px 6px; border-radius: 3px; }
+.readability-badge { display: inline-block; margin: 0 4px 2px 0; padding: 0 5px; border-radius: 3px; background: #222; color: #fff; font: bold 11px/1.6 monospace; white-space: nowrap; }
+.readability-candidate { outline: 3px solid var(--readability-color); outline-offset: -3px; }
+.readability-candidate > .readability-badge { background: var(--readability-color); }
+.readability-top { outline: 5px solid #1565c0 !important; box-shadow: 0 0 0 9999px rgba(21, 101, 192, 0.04); }
+.readability-top > .readability-badge { background: #1565c0; }
+.readability-sibling { background: rgba(102, 187, 106, 0.18) !important; outline: 2px dashed #2e7d32; }
+.readability-converted { outline: 1px dotted #6a1b9a; }
+.readability-removed { opacity: 0.35; filter: grayscale(1); background: repeating-linear-gradient(45deg, #eee, #eee 6px, #ddd 6px, #ddd 12px) !important; }
+.readability-removed > .readability-badge { background: #c62828; }
+"#;
+
+const LEGEND: [(&str, &str); 5] = [
+    ("#1565c0", "top candidate"),
+    ("hsl(120, 70%, 40%)", "candidate (color by score)"),
+    ("#2e7d32", "merged sibling"),
+    ("#6a1b9a", "converted"),
+    ("#c62828", "removed"),
+];
+
+// elements that can't hold the badge, it is put in front of them instead
+const VOID_ELEMS: [&str; 8] = [
+    "img", "br", "hr", "input", "iframe", "embed", "video", "svg",
+];
+
+// removed elements that aren't rendered, they are dropped from the view
+// instead of marked, so scripts of the page don't run in it
+const HIDDEN_ELEMS: [&str; 3] = ["script", "style", "link"];
+// removed elements that load third party content, they are replaced by a
+// placeholder naming them
+const EMBED_ELEMS: [&str; 3] = ["iframe", "embed", "object"];
+
+// Renders the source document with the decisions of a `Trace` drawn onto it:
+// scored candidates get an outline colored by score and a badge, the top
+// candidate and the merged siblings are highlighted and removed nodes are
+// greyed out instead of deleted.
+pub struct Annotator;
+
+impl Annotator {
+    // Tag every element with a sequential id, so the decisions taken on the
+    // working copies of the document can be mapped back onto this one.
+    pub(crate) fn stamp(document: &Document) {
+        fn stamp_node(node: &Node, next_id: &mut usize) {
+            for mut child in node.get_child_elements() {
+                _ = child.set_attribute(NODE_ID_ATTR, &next_id.to_string());
+                *next_id += 1;
+                stamp_node(&child, next_id);
+            }
+        }
+
+        if let Some(mut root) = document.get_root_element() {
+            _ = root.set_attribute(NODE_ID_ATTR, "0");
+            stamp_node(&root, &mut 1);
+        }
+    }
+
+    // Annotate a stamped document and serialize it as standalone html. Only
+    // the decisions of the pass that produced the article are shown.
+    pub(crate) fn render(view: &Document, trace: &Trace) -> Option<String> {
+        let mut root = view.get_root_element()?;
+        let mut nodes = HashMap::new();
+        collect_nodes(&root, &mut nodes);
+
+        let selected = trace.passes.iter().position(|pass| pass.selected);
+        if let Some(pass) = selected.map(|index| &trace.passes[index]) {
+            let max_score = pass
+                .candidates
+                .iter()
+                .map(|candidate| candidate.content_score)
+                .fold(0.0, f64::max);
+
+            let top_candidate = pass.top_candidate.as_ref();
+            for candidate in &pass.candidates {
+                if top_candidate.is_some_and(|top| top.node == candidate.node) {
+                    continue;
+                }
+                if let Some(node) = candidate_node(candidate, &mut nodes) {
+                    let ratio = if max_score > 0.0 {
+                        (candidate.content_score / max_score).clamp(0.0, 1.0)
+                    } else {
+                        0.0
+                    };
+                    let style = format!("--readability-color: hsl({:.0}, 70%, 40%)", ratio * 120.0);
+                    annotate(
+                        view,
+                        node,
+                        "readability-candidate",
+                        &format!("{:.1}", candidate.content_score),
+                        Some(&style),
+                    );
+                }
+            }
+
+            if let Some(top_candidate) = top_candidate {
+                if let Some(node) = candidate_node(top_candidate, &mut nodes) {
+                    let label = format!("top candidate {:.1}", top_candidate.content_score);
+                    annotate(view, node, "readability-top", &label, None);
+                }
+            }
+        }
+
+        for event in trace
+            .events
+            .iter()
+            .filter(|event| event.pass.is_none() || event.pass == selected)
+        {
+            let Some(node) = event.node.and_then(|id| nodes.get_mut(&id)) else {
+                continue;
+            };
+            let name = node.get_name().to_lowercase();
+            if event.action == TraceAction::Remove && HIDDEN_ELEMS.contains(&name.as_str()) {
+                node.unlink();
+                continue;
+            }
+            if event.action == TraceAction::Remove && EMBED_ELEMS.contains(&name.as_str()) {
+                if let Ok(mut placeholder) = Node::new("span", None, view) {
+                    _ = placeholder.append_text(&format!("<{name}>"));
+                    annotate(
+                        view,
+                        &mut placeholder,
+                        "readability-removed",
+                        event.rule,
+                        None,
+                    );
+                    _ = node.add_prev_sibling(&mut placeholder);
+                }
+                node.unlink();
+                continue;
+            }
+            let class = match event.action {
+                TraceAction::Remove => "readability-removed",
+                TraceAction::Convert => "readability-converted",
+                TraceAction::Keep => "readability-sibling",
+            };
+            annotate(view, node, class, event.rule, None);
+        }
+
+        // event handlers of the page must not run in the view either
+        for node in nodes.values_mut() {
+            _ = node.remove_attribute(NODE_ID_ATTR);
+            let handlers = node
+                .get_attributes()
+                .into_keys()
+                .filter(|name| name.to_lowercase().starts_with("on"));
+            for name in handlers.collect::<Vec<_>>() {
+                _ = node.remove_attribute(&name);
+            }
+        }
+        add_chrome(view, &mut root);
+
+        Some(view.to_string_with_options(SaveOptions {
+            as_html: true,
+            ..SaveOptions::default()
+        }))
+    }
+}
+
+fn collect_nodes(node: &Node, nodes: &mut HashMap<usize, Node>) {
+    if let Some(id) = node
+        .get_attribute(NODE_ID_ATTR)
+        .and_then(|id| id.parse().ok())
+    {
+        nodes.insert(id, node.clone());
+    }
+
+    for child in node.get_child_elements() {
+        collect_nodes(&child, nodes);
+    }
+}
+
+fn candidate_node<'a>(
+    candidate: &TraceCandidate,
+    nodes: &'a mut HashMap<usize, Node>,
+) -> Option<&'a mut Node> {
+    candidate.node.and_then(|id| nodes.get_mut(&id))
+}
+
+fn annotate(view: &Document, node: &mut Node, class: &str, label: &str, style: Option<&str>) {
+    let classes = match node.get_attribute("class") {
+        Some(classes) => format!("{classes} {class}"),
+        None => class.to_owned(),
+    };
+    _ = node.set_attribute("class", &classes);
+
+    if let Some(style) = style {
+        let style = match node.get_attribute("style") {
+            Some(existing) => format!("{existing}; {style}"),
+            None => style.to_owned(),
+        };
+        _ = node.set_attribute("style", &style);
+    }
+
+    let Ok(mut badge) = Node::new("span", None, view) else {
+        return;
+    };
+    _ = badge.set_attribute("class", "readability-badge");
+    _ = badge.append_text(label);
+
+    if VOID_ELEMS.contains(&node.get_name().to_lowercase().as_str()) {
+        _ = node.add_prev_sibling(&mut badge);
+    } else if let Some(mut first_child) = node.get_first_child() {
+        _ = first_child.add_prev_sibling(&mut badge);
+    } else {
+        _ = node.add_child(&mut badge);
+    }
+}
+
+// Add the stylesheet and a legend explaining the colors.
+fn add_chrome(view: &Document, root: &mut Node) {
+    let head = root
+        .get_child_elements()
+        .into_iter()
+        .find(|node| node.get_name().eq_ignore_ascii_case("head"));
+    let mut head = match head {
+        Some(head) => head,
+        None => match Node::new("head", None, view) {
+            Ok(mut head) => {
+                match root.get_first_child() {
+                    Some(mut first_child) => _ = first_child.add_prev_sibling(&mut head),
+                    None => _ = root.add_child(&mut head),
+                }
+                head
+            }
+            Err(()) => return,
+        },
+    };
+    if let Ok(mut style) = Node::new("style", None, view) {
+        _ = style.append_text(STYLE);
+        _ = head.add_child(&mut style);
+    }
+
+    let body = root
+        .get_child_elements()
+        .into_iter()
+        .find(|node| node.get_name().eq_ignore_ascii_case("body"));
+    let (Some(mut body), Ok(mut legend)) = (body, Node::new("div", None, view)) else {
+        return;
+    };
+    _ = legend.set_attribute("class", "readability-legend");
+    for (color, label) in LEGEND {
+        if let Ok(mut entry) = Node::new("span", None, view) {
+            _ = entry.set_attribute("style", &format!("background: {color}"));
+            _ = entry.append_text(label);
+            _ = legend.add_child(&mut entry);
+        }
+    }
+    match body.get_first_child() {
+        Some(mut first_child) => _ = first_child.add_prev_sibling(&mut legend),
+        None => _ = body.add_child(&mut legend),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::readability::{ExtractOptions, Readability};
+
+    #[tokio::test]
+    async fn annotate_document() {
+        let paragraph = "Lorem ipsum dolor sit amet, consectetur adipiscing elit, sed do eiusmod tempor incididunt ut labore et dolore magna aliqua. ";
+        let story = format!("<p>{paragraph}</p>").repeat(6);
+        let html = format!(
+            r#"<html><head><title>Story</title></head><body>
+                <div class="sidebar"><p>{paragraph}</p></div>
+                <div id="story">{story}</div>
+                <aside><p>Related</p></aside>
+                <script>track();</script>
+            </body></html>"#
+        );
+
+        let annotated = Readability::extract_annotated(&html, None, &ExtractOptions::default())
+            .await
+            .unwrap();
+        assert!(annotated.contains(r#"<div class="sidebar readability-removed""#));
+        assert!(annotated.contains(r#"<span class="readability-badge">unlikely_candidate</span>"#));
+        assert!(annotated.contains(r#"<div id="story" class="readability-top">"#));
+        assert!(annotated.contains(r#"<span class="readability-badge">top candidate 60.0</span>"#));
+        assert!(annotated.contains(r#"<span class="readability-badge">15.0</span>"#));
+        assert!(annotated.contains(r#"<aside class="readability-removed"><span class="readability-badge">aside</span><p>Related</p></aside>"#));
+        assert!(!annotated.contains("track()"));
+        assert!(!annotated.contains("readability_node"));
+        assert!(annotated.contains("<style>"));
+
+        let article = Readability::extract_article(
+            &html,
+            None,
+            &ExtractOptions::builder().annotate(true).build().unwrap(),
+        )
+        .await
+        .unwrap();
+        assert!(article.annotated_html.is_some());
+        assert!(article.trace.is_none());
+        assert!(!article.content.contains("readability_node"));
+
+        // embeds are replaced by a placeholder and event handlers dropped
+        let html = html.replace(
+            r#"<div id="story">"#,
+            r#"<div id="story" onclick="track()"><iframe src="https://ads.example/frame"></iframe>"#,
+        );
+        let annotated = Readability::extract_annotated(&html, None, &ExtractOptions::default())
+            .await
+            .unwrap();
+        assert!(annotated.contains(r#"<span class="readability-removed"><span class="readability-badge">iframe</span>&lt;iframe&gt;</span>"#));
+        assert!(!annotated.contains("ads.example"));
+        assert!(!annotated.contains("onclick"));
+        assert!(!annotated.contains("track()"));
+    }
+}
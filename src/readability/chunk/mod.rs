@@ -0,0 +1,523 @@
+use std::ops::Range;
+
+use libxml::tree::{Node, NodeType};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use tiktoken_rs::{
+    cl100k_base_singleton, o200k_base_singleton, p50k_base_singleton, p50k_edit_singleton,
+    r50k_base_singleton, CoreBPE,
+};
+
+use super::encoding::HtmlEncoding;
+use super::FullTextParserError;
+
+// elements whose text ends up in a unit of its own
+const BLOCK_ELEMS: &[&str] = &[
+    "address",
+    "article",
+    "aside",
+    "blockquote",
+    "body",
+    "dd",
+    "details",
+    "div",
+    "dl",
+    "dt",
+    "figcaption",
+    "figure",
+    "footer",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "header",
+    "html",
+    "li",
+    "main",
+    "ol",
+    "p",
+    "pre",
+    "section",
+    "table",
+    "tbody",
+    "td",
+    "tfoot",
+    "th",
+    "thead",
+    "tr",
+    "ul",
+];
+const IGNORED_ELEMS: &[&str] = &["head", "noscript", "script", "style", "template"];
+const UNIT_SEPARATOR: &str = "\n\n";
+
+static SENTENCE_END: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"[.!?]["'”’)]*\s+|[。！？]["'”’)」』]*\s*"#).expect("SENTENCE_END regex")
+});
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TokenEncoding {
+    // gpt-4, gpt-3.5-turbo and text-embedding-ada-002
+    #[default]
+    Cl100kBase,
+    // gpt-4o models
+    O200kBase,
+    // codex models, text-davinci-002 and text-davinci-003
+    P50kBase,
+    P50kEdit,
+    // gpt-3 models like davinci
+    R50kBase,
+}
+
+impl TokenEncoding {
+    // The singletons are shared by the whole process, so the lock is only held
+    // for a single encoding.
+    fn with_bpe<T>(self, f: impl FnOnce(&CoreBPE) -> T) -> T {
+        let bpe = match self {
+            Self::Cl100kBase => cl100k_base_singleton(),
+            Self::O200kBase => o200k_base_singleton(),
+            Self::P50kBase => p50k_base_singleton(),
+            Self::P50kEdit => p50k_edit_singleton(),
+            Self::R50kBase => r50k_base_singleton(),
+        };
+        let bpe = bpe.lock();
+        f(&bpe)
+    }
+
+    pub fn count_tokens(self, text: &str) -> usize {
+        self.with_bpe(|bpe| bpe.encode_ordinary(text).len())
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ChunkOptions {
+    pub encoding: TokenEncoding,
+    pub max_tokens: usize,
+    // number of tokens at the end of a chunk that are repeated at the start of
+    // the next one. Overlap never crosses a heading.
+    pub overlap_tokens: usize,
+}
+
+impl Default for ChunkOptions {
+    fn default() -> Self {
+        Self {
+            encoding: TokenEncoding::default(),
+            max_tokens: 512,
+            overlap_tokens: 0,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Chunk {
+    pub text: String,
+    pub token_count: usize,
+    // the headings (outermost first) the chunk is located under
+    pub heading_path: Vec<String>,
+    // character offsets of `text` in the text returned by `Chunker::text`
+    pub start: usize,
+    pub end: usize,
+}
+
+struct Unit {
+    range: Range<usize>,
+    heading_path: Vec<String>,
+    is_heading: bool,
+}
+
+// Splits article content into chunks of at most `max_tokens` tokens. Chunks
+// end at paragraph boundaries where possible and a heading always starts a new
+// chunk. Paragraphs that are too long on their own are split into sentences
+// and, if need be, words.
+pub struct Chunker {
+    text: String,
+    units: Vec<Unit>,
+    headings: Vec<(usize, String)>,
+}
+
+impl Chunker {
+    pub fn chunk_node(root: &Node, options: &ChunkOptions) -> Vec<Chunk> {
+        Self::new(root).chunks(options)
+    }
+
+    pub fn chunk_html(
+        html: &str,
+        options: &ChunkOptions,
+    ) -> Result<Vec<Chunk>, FullTextParserError> {
+        let document = HtmlEncoding::parse_utf8(html)?;
+        let root = document
+            .get_root_element()
+            .ok_or(FullTextParserError::Xml)?;
+
+        Ok(Self::chunk_node(&root, options))
+    }
+
+    // Plain text of `root` with blocks separated by blank lines. The offsets of
+    // the chunks refer to this text.
+    pub fn text(root: &Node) -> String {
+        Self::new(root).text
+    }
+
+    fn new(root: &Node) -> Self {
+        let mut chunker = Self {
+            text: String::new(),
+            units: Vec::new(),
+            headings: Vec::new(),
+        };
+        chunker.collect_units(root);
+        chunker
+    }
+
+    fn collect_units(&mut self, node: &Node) {
+        let mut inline = String::new();
+
+        for child in node.get_child_nodes() {
+            match child.get_type() {
+                Some(NodeType::TextNode) | Some(NodeType::CDataSectionNode) => {
+                    inline.push_str(&child.get_content());
+                    continue;
+                }
+                Some(NodeType::ElementNode) => {}
+                _ => continue,
+            }
+
+            let tag = child.get_name().to_lowercase();
+            if IGNORED_ELEMS.contains(&tag.as_str()) {
+                continue;
+            }
+            if !BLOCK_ELEMS.contains(&tag.as_str()) {
+                inline.push_str(&Self::inline_text(&child));
+                continue;
+            }
+
+            self.push_unit(&inline, false);
+            inline.clear();
+
+            if let Some(level) = Self::heading_level(&tag) {
+                let heading = Self::collapse_whitespace(&child.get_content());
+                if !heading.is_empty() {
+                    self.headings.retain(|(known, _)| *known < level);
+                    self.headings.push((level, heading.clone()));
+                    self.push_text(&heading, true);
+                }
+            } else if tag == "pre" {
+                // keep the formatting of code blocks
+                self.push_text(child.get_content().trim_matches('\n'), false);
+            } else if Self::has_block_children(&child) {
+                self.collect_units(&child);
+            } else {
+                self.push_unit(&Self::inline_text(&child), false);
+            }
+        }
+
+        self.push_unit(&inline, false);
+    }
+
+    fn push_unit(&mut self, text: &str, is_heading: bool) {
+        self.push_text(&Self::collapse_whitespace(text), is_heading);
+    }
+
+    fn push_text(&mut self, text: &str, is_heading: bool) {
+        if text.trim().is_empty() {
+            return;
+        }
+
+        if !self.text.is_empty() {
+            self.text.push_str(UNIT_SEPARATOR);
+        }
+        let start = self.text.len();
+        self.text.push_str(text);
+
+        self.units.push(Unit {
+            range: start..self.text.len(),
+            heading_path: self
+                .headings
+                .iter()
+                .map(|(_, heading)| heading.clone())
+                .collect(),
+            is_heading,
+        });
+    }
+
+    fn inline_text(node: &Node) -> String {
+        let tag = node.get_name().to_lowercase();
+        if IGNORED_ELEMS.contains(&tag.as_str()) {
+            return String::new();
+        }
+        if tag == "br" {
+            return "\n".into();
+        }
+
+        node.get_child_nodes()
+            .iter()
+            .map(|child| match child.get_type() {
+                Some(NodeType::ElementNode) => Self::inline_text(child),
+                Some(NodeType::TextNode) | Some(NodeType::CDataSectionNode) => child.get_content(),
+                _ => String::new(),
+            })
+            .collect()
+    }
+
+    fn has_block_children(node: &Node) -> bool {
+        node.get_child_nodes().iter().any(|child| {
+            child.get_type() == Some(NodeType::ElementNode)
+                && (BLOCK_ELEMS.contains(&child.get_name().to_lowercase().as_str())
+                    || Self::has_block_children(child))
+        })
+    }
+
+    fn heading_level(tag: &str) -> Option<usize> {
+        match tag {
+            "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => tag[1..].parse().ok(),
+            _ => None,
+        }
+    }
+
+    fn collapse_whitespace(text: &str) -> String {
+        text.split_whitespace().collect::<Vec<_>>().join(" ")
+    }
+
+    fn chunks(mut self, options: &ChunkOptions) -> Vec<Chunk> {
+        let max_tokens = options.max_tokens.max(1);
+        let count = |range: Range<usize>| options.encoding.count_tokens(&self.text[range]);
+
+        // units that don't fit into a chunk on their own are split up first
+        let units = std::mem::take(&mut self.units);
+        let mut pieces = Vec::new();
+        for unit in units {
+            if count(unit.range.clone()) <= max_tokens {
+                pieces.push(unit);
+                continue;
+            }
+
+            for range in self.split(unit.range.clone(), max_tokens, &count) {
+                pieces.push(Unit {
+                    range,
+                    heading_path: unit.heading_path.clone(),
+                    is_heading: unit.is_heading,
+                });
+            }
+        }
+
+        let span = |first: usize, last: usize| pieces[first].range.start..pieces[last].range.end;
+
+        // every piece and the separator after it is only encoded once, spans
+        // are estimated from the running total of their token counts
+        let mut totals = vec![0];
+        let mut gaps = Vec::with_capacity(pieces.len());
+        for (i, piece) in pieces.iter().enumerate() {
+            let gap = pieces
+                .get(i + 1)
+                .map_or(0, |next| count(piece.range.end..next.range.start));
+            gaps.push(gap);
+            totals.push(totals[i] + count(piece.range.clone()) + gap);
+        }
+        let estimate = |first: usize, last: usize| totals[last + 1] - totals[first] - gaps[last];
+
+        let mut chunks = Vec::new();
+        let mut start = 0;
+
+        while start < pieces.len() {
+            let mut end = start;
+            while end + 1 < pieces.len() {
+                let next = &pieces[end + 1];
+                let has_content = pieces[start..=end].iter().any(|piece| !piece.is_heading);
+                if next.is_heading && has_content || estimate(start, end + 1) > max_tokens {
+                    break;
+                }
+                end += 1;
+            }
+
+            // tokens can merge across piece boundaries, so the estimate may be
+            // slightly off
+            let mut token_count = count(span(start, end));
+            while token_count > max_tokens && end > start {
+                end -= 1;
+                token_count = count(span(start, end));
+            }
+
+            let range = span(start, end);
+            chunks.push(Chunk {
+                text: self.text[range.clone()].to_string(),
+                token_count,
+                heading_path: pieces[start].heading_path.clone(),
+                start: self.text[..range.start].chars().count(),
+                end: self.text[..range.end].chars().count(),
+            });
+
+            // step back over the last pieces of this chunk to create the overlap,
+            // as long as the next chunk still gets at least one new piece
+            let chunk_start = start;
+            let next = end + 1;
+            start = next;
+            if next < pieces.len() && !pieces[next].is_heading {
+                while start - 1 > chunk_start
+                    && !pieces[start - 1].is_heading
+                    && estimate(start - 1, end) <= options.overlap_tokens
+                    && estimate(start - 1, next) <= max_tokens
+                {
+                    start -= 1;
+                }
+            }
+        }
+
+        chunks
+    }
+
+    // Split a piece of text at sentence ends, then at whitespace and if all
+    // else fails at character boundaries, until every part fits `max_tokens`.
+    fn split(
+        &self,
+        range: Range<usize>,
+        max_tokens: usize,
+        count: &dyn Fn(Range<usize>) -> usize,
+    ) -> Vec<Range<usize>> {
+        let text = &self.text[range.clone()];
+        let offset = range.start;
+
+        let sentences = SENTENCE_END
+            .find_iter(text)
+            .map(|end| end.end())
+            .chain(std::iter::once(text.len()))
+            .scan(0, |start, end| {
+                let part = *start..end;
+                *start = end;
+                Some(part)
+            });
+        let words = text.split_whitespace().map(|word| {
+            let start = word.as_ptr() as usize - text.as_ptr() as usize;
+            start..start + word.len()
+        });
+        let chars = text
+            .char_indices()
+            .map(|(start, c)| start..start + c.len_utf8());
+
+        for parts in [
+            sentences.collect::<Vec<_>>(),
+            words.collect(),
+            chars.collect(),
+        ] {
+            let parts = parts
+                .into_iter()
+                .map(|part| offset + part.start..offset + part.end)
+                .collect::<Vec<_>>();
+            if parts.len() < 2 {
+                continue;
+            }
+
+            // the parts are merged again when the chunks are assembled
+            let mut ranges: Vec<Range<usize>> = Vec::new();
+            for part in parts {
+                if count(part.clone()) > max_tokens {
+                    ranges.extend(self.split(part, max_tokens, count));
+                } else {
+                    ranges.push(part);
+                }
+            }
+
+            return ranges
+                .into_iter()
+                .map(|range| Self::trim_range(&self.text, range))
+                .filter(|range| !range.is_empty())
+                .collect();
+        }
+
+        vec![range]
+    }
+
+    fn trim_range(text: &str, range: Range<usize>) -> Range<usize> {
+        let part = &text[range.clone()];
+        let start = range.start + (part.len() - part.trim_start().len());
+        let end = range.start + part.trim_end().len();
+        start..end.max(start)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ChunkOptions, Chunker, HtmlEncoding, TokenEncoding};
+
+    const HTML: &str = r#"<article><div id="readability-page-1">
+        <h1>Title</h1>
+        <p>First paragraph with a few words.</p>
+        <p>Second paragraph with a few more words.</p>
+        <h2>Section</h2>
+        <p>Third paragraph. It has two sentences.</p>
+        <ul><li>an item</li><li>another item</li></ul>
+        <h2>Long</h2>
+        <p>PLACEHOLDER</p>
+    </div></article>"#;
+
+    #[test]
+    fn chunk_article() {
+        let long = "This sentence is repeated to build a long paragraph. ".repeat(20);
+        let html = HTML.replace("PLACEHOLDER", &long);
+        let options = ChunkOptions {
+            encoding: TokenEncoding::Cl100kBase,
+            max_tokens: 30,
+            overlap_tokens: 0,
+        };
+
+        let chunks = Chunker::chunk_html(&html, &options).unwrap();
+        let document = HtmlEncoding::parse_utf8(&html).unwrap();
+        let text = Chunker::text(&document.get_root_element().unwrap());
+
+        assert_eq!(
+            chunks[0].text,
+            "Title\n\nFirst paragraph with a few words.\n\nSecond paragraph with a few more words."
+        );
+        assert_eq!(chunks[0].heading_path, vec!["Title"]);
+        assert!(chunks[1].text.starts_with("Section\n\nThird paragraph."));
+        assert_eq!(chunks[1].heading_path, vec!["Title", "Section"]);
+        assert!(chunks[2].text.starts_with("Long\n\nThis sentence"));
+        assert_eq!(chunks.last().unwrap().heading_path, vec!["Title", "Long"]);
+
+        for chunk in &chunks {
+            assert!(chunk.token_count <= 30, "{chunk:?}");
+            assert_eq!(
+                options.encoding.count_tokens(&chunk.text),
+                chunk.token_count
+            );
+            let slice = text
+                .chars()
+                .skip(chunk.start)
+                .take(chunk.end - chunk.start)
+                .collect::<String>();
+            assert_eq!(slice, chunk.text);
+        }
+
+        let overlapping = Chunker::chunk_html(
+            &html,
+            &ChunkOptions {
+                overlap_tokens: 12,
+                ..options
+            },
+        )
+        .unwrap();
+        let long_chunks = overlapping
+            .iter()
+            .filter(|chunk| chunk.heading_path.last().map(String::as_str) == Some("Long"))
+            .collect::<Vec<_>>();
+        assert!(long_chunks.len() > 2);
+        for pair in long_chunks.windows(2) {
+            assert!(pair[1].start < pair[0].end, "{:?}", pair);
+        }
+
+        let chunks = Chunker::chunk_html(
+            &html,
+            &ChunkOptions {
+                encoding: TokenEncoding::O200kBase,
+                ..options
+            },
+        )
+        .unwrap();
+        for chunk in &chunks {
+            assert!(chunk.token_count <= 30, "{chunk:?}");
+            assert_eq!(
+                TokenEncoding::O200kBase.count_tokens(&chunk.text),
+                chunk.token_count
+            );
+        }
+    }
+}
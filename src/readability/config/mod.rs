@@ -0,0 +1,433 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use thiserror::Error;
+use tokio::fs;
+use url::Url;
+
+use super::util::Util;
+
+#[derive(Error, Debug)]
+pub enum ConfigError {
+    #[error("IO error")]
+    IO(#[from] std::io::Error),
+    #[error("Parse error in line {line}: {message}")]
+    Parse { line: usize, message: String },
+    #[error("Failed to parse config '{file}'")]
+    File {
+        file: String,
+        source: Box<ConfigError>,
+    },
+    #[error("Unknown Error")]
+    Unknown,
+}
+
+#[derive(Clone, Debug)]
+pub struct Replace {
+    pub to_replace: String,
+    pub replace_with: String,
+}
+
+#[derive(Clone, Debug)]
+pub struct Header {
+    pub name: String,
+    pub value: String,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct ConfigEntry {
+    pub xpath_title: Vec<String>,
+    pub xpath_author: Vec<String>,
+    pub xpath_date: Vec<String>,
+    pub xpath_body: Vec<String>,
+    pub xpath_strip: Vec<String>,
+    // elements a headless browser waits for before capturing the page
+    pub xpath_wait: Vec<String>,
+    pub strip_id_or_class: Vec<String>,
+    pub strip_image_src: Vec<String>,
+    pub replace: Vec<Replace>,
+    pub header: Vec<Header>,
+    pub single_page_link: Option<String>,
+    pub next_page_link: Option<String>,
+}
+
+impl ConfigEntry {
+    pub async fn parse_path(config_path: &Path) -> Result<ConfigEntry, ConfigError> {
+        let content = fs::read_to_string(config_path).await?;
+        Self::parse_str(&content)
+    }
+
+    // Parse all `*.txt` rule files of a directory (e.g. a checkout of
+    // https://github.com/fivefilters/ftr-site-config). The map is keyed by the
+    // file name without extension, which by convention is the host name.
+    pub async fn parse_directory(
+        config_dir: &Path,
+    ) -> Result<HashMap<String, ConfigEntry>, ConfigError> {
+        let mut configs = HashMap::new();
+        let mut dir = fs::read_dir(config_dir).await?;
+
+        while let Some(entry) = dir.next_entry().await? {
+            if !Util::check_extension(&entry, "txt") {
+                continue;
+            }
+
+            let file_name = entry.file_name().to_string_lossy().to_string();
+            let config =
+                Self::parse_path(&entry.path())
+                    .await
+                    .map_err(|error| ConfigError::File {
+                        file: file_name.clone(),
+                        source: Box::new(error),
+                    })?;
+            let key = file_name.trim_end_matches(".txt").to_string();
+            configs.insert(key, config);
+        }
+
+        Ok(configs)
+    }
+
+    pub fn parse_str(config: &str) -> Result<ConfigEntry, ConfigError> {
+        let mut xpath_title: Vec<String> = Vec::new();
+        let mut xpath_author: Vec<String> = Vec::new();
+        let mut xpath_date: Vec<String> = Vec::new();
+        let mut xpath_body: Vec<String> = Vec::new();
+        let mut xpath_strip: Vec<String> = Vec::new();
+        let mut xpath_wait: Vec<String> = Vec::new();
+        let mut strip_id_or_class: Vec<String> = Vec::new();
+        let mut strip_image_src: Vec<String> = Vec::new();
+        let mut replace_vec: Vec<Replace> = Vec::new();
+        let mut header_vec: Vec<Header> = Vec::new();
+        let mut next_page_link: Option<String> = None;
+        let mut single_page_link: Option<String> = None;
+
+        let title = "title:";
+        let body = "body:";
+        let date = "date:";
+        let author = "author:";
+        let strip = "strip:";
+        let wait_for = "wait_for:";
+        let strip_id = "strip_id_or_class:";
+        let strip_img = "strip_image_src:";
+        let single_page = "single_page_link:";
+        let next_page = "next_page_link:";
+        let find = "find_string:";
+        let replace = "replace_string:";
+        let replace_single = "replace_string(";
+        let http_header = "http_header(";
+
+        // find_string: has to be followed by a replace_string: line
+        let mut pending_find: Option<(usize, String)> = None;
+
+        for (index, line) in config.lines().enumerate() {
+            let line_number = index + 1;
+            let line = line.trim();
+            if line.starts_with('#') || line.is_empty() {
+                continue;
+            }
+
+            if let Some((find_line, to_replace)) = pending_find.take() {
+                if !line.starts_with(replace) {
+                    return Err(ConfigError::Parse {
+                        line: find_line,
+                        message: "'find_string' is not followed by 'replace_string'".into(),
+                    });
+                }
+
+                let replace_with = Util::str_extract_value(replace, line);
+                replace_vec.push(Replace {
+                    to_replace,
+                    replace_with: replace_with.to_string(),
+                });
+                continue;
+            }
+
+            if line.starts_with(replace_single) || line.starts_with(http_header) {
+                let identifier = if line.starts_with(http_header) {
+                    http_header
+                } else {
+                    replace_single
+                };
+                let (argument, value) =
+                    line[identifier.len()..].split_once("):").ok_or_else(|| {
+                        ConfigError::Parse {
+                            line: line_number,
+                            message: format!("expected '{identifier}...): value'"),
+                        }
+                    })?;
+
+                if identifier == http_header {
+                    header_vec.push(Header {
+                        name: argument.trim().to_string(),
+                        value: value.trim().to_string(),
+                    });
+                } else {
+                    replace_vec.push(Replace {
+                        to_replace: argument.to_string(),
+                        replace_with: value.trim().to_string(),
+                    });
+                }
+                continue;
+            }
+
+            let (directive, value) = line.split_once(':').ok_or_else(|| ConfigError::Parse {
+                line: line_number,
+                message: "expected 'directive: value'".into(),
+            })?;
+
+            let requires_value = [
+                title,
+                body,
+                date,
+                author,
+                strip,
+                wait_for,
+                single_page,
+                next_page,
+            ]
+            .iter()
+            .any(|d| d.trim_end_matches(':') == directive.trim());
+            if requires_value && value.trim().is_empty() {
+                return Err(ConfigError::Parse {
+                    line: line_number,
+                    message: format!("missing value for '{}'", directive.trim()),
+                });
+            }
+
+            extract_vec_multi!(line, title, xpath_title);
+            extract_vec_multi!(line, body, xpath_body);
+            extract_vec_multi!(line, date, xpath_date);
+            extract_vec_multi!(line, author, xpath_author);
+            extract_vec_multi!(line, wait_for, xpath_wait);
+
+            extract_vec_single!(line, strip, xpath_strip);
+            extract_vec_single!(line, strip_id, strip_id_or_class);
+            extract_vec_single!(line, strip_img, strip_image_src);
+
+            extract_option_single!(line, single_page, single_page_link);
+            extract_option_single!(line, next_page, next_page_link);
+
+            if line.starts_with(find) {
+                let to_replace = Util::str_extract_value(find, line);
+                pending_find = Some((line_number, to_replace.to_string()));
+                continue;
+            }
+
+            if line.starts_with(replace) {
+                return Err(ConfigError::Parse {
+                    line: line_number,
+                    message: "'replace_string' without preceding 'find_string'".into(),
+                });
+            }
+
+            // tidy, prune, test_url, autodetect_on_failure, login options etc. are not supported
+            log::debug!("Ignoring site config directive '{}'", directive.trim());
+        }
+
+        if let Some((find_line, _)) = pending_find {
+            return Err(ConfigError::Parse {
+                line: find_line,
+                message: "'find_string' is not followed by 'replace_string'".into(),
+            });
+        }
+
+        Ok(ConfigEntry {
+            xpath_title,
+            xpath_author,
+            xpath_date,
+            xpath_body,
+            xpath_strip,
+            xpath_wait,
+            strip_id_or_class,
+            strip_image_src,
+            replace: replace_vec,
+            header: header_vec,
+            single_page_link,
+            next_page_link,
+        })
+    }
+}
+
+// Site configs keyed by host name, following the ftr-site-config file naming:
+// `example.com` applies to example.com and www.example.com, `.example.com`
+// applies to every subdomain of example.com and `global` to all sites.
+#[derive(Clone, Debug, Default)]
+pub struct ConfigCollection {
+    entries: HashMap<String, ConfigEntry>,
+}
+
+impl ConfigCollection {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub async fn parse_directory(config_dir: &Path) -> Result<Self, ConfigError> {
+        let mut collection = Self::new();
+        for (key, config) in ConfigEntry::parse_directory(config_dir).await? {
+            collection.insert(&key, config);
+        }
+        Ok(collection)
+    }
+
+    pub fn insert(&mut self, key: &str, config: ConfigEntry) {
+        self.entries
+            .insert(key.trim_start_matches("www.").into(), config);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn global(&self) -> Option<&ConfigEntry> {
+        self.entries.get("global")
+    }
+
+    pub fn get(&self, url: &Url) -> Option<&ConfigEntry> {
+        let host = url.host_str()?.trim_start_matches("www.");
+
+        if let Some(config) = self.entries.get(host) {
+            return Some(config);
+        }
+
+        // walk up the parent domains looking for a wildcard entry,
+        // but stop before the top level domain
+        let mut domain = host;
+        while domain.contains('.') {
+            if let Some(config) = self.entries.get(&format!(".{domain}")) {
+                log::debug!("Using site config '.{domain}' for host '{host}'");
+                return Some(config);
+            }
+
+            domain = match domain.split_once('.') {
+                Some((_, parent)) => parent,
+                None => break,
+            };
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use url::Url;
+
+    use super::{ConfigCollection, ConfigEntry, ConfigError};
+
+    #[test]
+    fn collection_lookup() {
+        let mut collection = ConfigCollection::new();
+        let mut site = ConfigEntry::default();
+        site.xpath_body.push("//article".into());
+        let mut wildcard = ConfigEntry::default();
+        wildcard.xpath_body.push("//main".into());
+        collection.insert("www.example.com", site);
+        collection.insert(".example.com", wildcard);
+        collection.insert("global", ConfigEntry::default());
+
+        let body = |url: &str| {
+            collection
+                .get(&Url::parse(url).unwrap())
+                .map(|config| config.xpath_body[0].clone())
+        };
+
+        assert_eq!(body("https://example.com/a").as_deref(), Some("//article"));
+        assert_eq!(
+            body("https://www.example.com/a").as_deref(),
+            Some("//article")
+        );
+        assert_eq!(
+            body("https://news.example.com/a").as_deref(),
+            Some("//main")
+        );
+        assert_eq!(body("https://a.b.example.com/a").as_deref(), Some("//main"));
+        assert_eq!(body("https://example.org/a"), None);
+        assert!(collection.global().is_some());
+    }
+
+    #[tokio::test]
+    async fn collection_from_directory() {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("resources/tests/site_config");
+        let collection = ConfigCollection::parse_directory(&dir).await.unwrap();
+
+        let body = |url: &str| {
+            collection
+                .get(&Url::parse(url).unwrap())
+                .map(|config| config.xpath_body[0].clone())
+        };
+
+        assert_eq!(body("https://example.com/a").as_deref(), Some("//article"));
+        assert_eq!(
+            body("https://www.example.com/a").as_deref(),
+            Some("//article")
+        );
+        assert_eq!(
+            body("https://news.example.org/a").as_deref(),
+            Some("//main")
+        );
+        assert_eq!(
+            collection.global().map(|config| config.xpath_strip.clone()),
+            Some(vec!["//nav".to_string()])
+        );
+    }
+
+    #[test]
+    fn parse_str() {
+        let config = r#"
+# example.com
+title: //h1[@class='headline'] | //meta[@property='og:title']/@content
+author: //span[@class='author']
+body: //div[@id='article-body']
+strip: //div[@class='ad']
+wait_for: //div[@id='article-body']/p
+strip_id_or_class: newsletter
+strip_image_src: /pixel.gif
+replace_string(<br /><br />): </p><p>
+find_string: <noscript>
+replace_string: <div>
+http_header(user-agent): Mozilla/5.0
+next_page_link: //a[@rel='next']
+prune: no
+test_url: https://example.com/article
+"#;
+
+        let config = ConfigEntry::parse_str(config).unwrap();
+        assert_eq!(
+            config.xpath_title,
+            vec![
+                "//h1[@class='headline']",
+                "//meta[@property='og:title']/@content"
+            ]
+        );
+        assert_eq!(config.xpath_author, vec!["//span[@class='author']"]);
+        assert_eq!(config.xpath_body, vec!["//div[@id='article-body']"]);
+        assert_eq!(config.xpath_strip, vec!["//div[@class='ad']"]);
+        assert_eq!(config.xpath_wait, vec!["//div[@id='article-body']/p"]);
+        assert_eq!(config.strip_id_or_class, vec!["newsletter"]);
+        assert_eq!(config.strip_image_src, vec!["/pixel.gif"]);
+        assert_eq!(config.replace.len(), 2);
+        assert_eq!(config.replace[0].to_replace, "<br /><br />");
+        assert_eq!(config.replace[0].replace_with, "</p><p>");
+        assert_eq!(config.replace[1].to_replace, "<noscript>");
+        assert_eq!(config.replace[1].replace_with, "<div>");
+        assert_eq!(config.header[0].name, "user-agent");
+        assert_eq!(config.header[0].value, "Mozilla/5.0");
+        assert_eq!(config.next_page_link.as_deref(), Some("//a[@rel='next']"));
+        assert!(config.single_page_link.is_none());
+    }
+
+    #[test]
+    fn parse_error_line() {
+        let config = "title: //h1\n\nfind_string: foo\nbody: //div\n";
+        match ConfigEntry::parse_str(config) {
+            Err(ConfigError::Parse { line, .. }) => assert_eq!(line, 3),
+            _ => panic!("expected parse error"),
+        }
+
+        match ConfigEntry::parse_str("title: //h1\nthis is not a directive") {
+            Err(ConfigError::Parse { line, .. }) => assert_eq!(line, 2),
+            _ => panic!("expected parse error"),
+        }
+    }
+}
@@ -0,0 +1,385 @@
+use std::thread;
+use std::time::Duration;
+
+use headless_chrome::browser::tab::Tab;
+use libxml::tree::Document;
+use libxml::xpath::Context;
+use serde_json::{json, Value};
+
+use super::util::Util;
+use super::{FullTextParserError, Trace};
+
+// hand made banners, named after what they are. Class names have to match
+// as a whole and the element has to overlay the page, so content like
+// `<main class="has-cookie-notice">` stays.
+const COOKIE_BANNER: &str = concat!(
+    "//*[not(self::html or self::body)]",
+    "[contains(concat(' ', normalize-space(@class), ' '), ' cookie-banner ') or @id='cookie-banner'",
+    " or contains(concat(' ', normalize-space(@class), ' '), ' cookie-notice ') or @id='cookie-notice'",
+    " or contains(concat(' ', normalize-space(@class), ' '), ' cookie-consent ') or @id='cookie-consent']",
+    "[@role='dialog' or @role='alertdialog' or @aria-modal='true'",
+    " or contains(translate(@style, ' ', ''), 'position:fixed')]",
+);
+
+// (name, detect, accept, reject, remove) of common consent management
+// platforms. Rules are XPaths, so they work in the browser as well as on
+// static html.
+const CONSENT_RULES: &[(&str, &str, &str, &str, &[&str])] = &[
+    (
+        "onetrust",
+        "//*[@id='onetrust-consent-sdk']",
+        "//button[@id='onetrust-accept-btn-handler']",
+        "//button[@id='onetrust-reject-all-handler']",
+        &["//*[@id='onetrust-consent-sdk']"],
+    ),
+    (
+        "cookiebot",
+        "//*[@id='CybotCookiebotDialog']",
+        "//*[@id='CybotCookiebotDialogBodyLevelButtonLevelOptinAllowAll' or @id='CybotCookiebotDialogBodyButtonAccept']",
+        "//*[@id='CybotCookiebotDialogBodyButtonDecline']",
+        &[
+            "//*[@id='CybotCookiebotDialog']",
+            "//*[@id='CybotCookiebotDialogBodyUnderlay']",
+        ],
+    ),
+    (
+        "quantcast",
+        "//*[@id='qc-cmp2-container']",
+        "//*[@id='qc-cmp2-ui']//button[@mode='primary']",
+        "//*[@id='qc-cmp2-ui']//button[@mode='secondary']",
+        &["//*[@id='qc-cmp2-container']"],
+    ),
+    (
+        "didomi",
+        "//*[@id='didomi-host']",
+        "//button[@id='didomi-notice-agree-button']",
+        "//button[@id='didomi-notice-disagree-button']",
+        &["//*[@id='didomi-host']"],
+    ),
+    (
+        "trustarc",
+        "//*[@id='truste-consent-track'] | //iframe[contains(@src, 'consent.trustarc.com')]",
+        "//button[@id='truste-consent-button']",
+        "//button[@id='truste-consent-required']",
+        &[
+            "//*[@id='truste-consent-track']",
+            concat!(
+                "//div[contains(concat(' ', normalize-space(@class), ' '), ' truste_overlay ')",
+                " or contains(concat(' ', normalize-space(@class), ' '), ' truste_box_overlay ')]",
+            ),
+        ],
+    ),
+    // the buttons of these live in a shadow root or a cross origin iframe
+    (
+        "usercentrics",
+        "//*[@id='usercentrics-root' or @id='usercentrics-cmp-ui']",
+        "",
+        "",
+        &["//*[@id='usercentrics-root' or @id='usercentrics-cmp-ui']"],
+    ),
+    (
+        "sourcepoint",
+        "//div[starts-with(@id, 'sp_message_container')]",
+        "",
+        "",
+        &["//div[starts-with(@id, 'sp_message_container')]"],
+    ),
+    ("cookie_banner", COOKIE_BANNER, "", "", &[COOKIE_BANNER]),
+];
+
+// Clicks the first node matching the XPath, answers whether there was one.
+const CLICK_SCRIPT: &str = r#"(xpath => {
+    const node = document.evaluate(xpath, document, null, XPathResult.FIRST_ORDERED_NODE_TYPE, null).singleNodeValue;
+    if (node === null) {
+        return false;
+    }
+    node.click();
+    return true;
+})"#;
+
+// Removes all nodes matching the XPaths and re-enables scrolling, which
+// consent walls disable on the document.
+const REMOVE_SCRIPT: &str = r#"(xpaths => {
+    let removed = 0;
+    for (const xpath of xpaths) {
+        const result = document.evaluate(xpath, document, null, XPathResult.ORDERED_NODE_SNAPSHOT_TYPE, null);
+        for (let i = 0; i < result.snapshotLength; i++) {
+            result.snapshotItem(i).remove();
+            removed += 1;
+        }
+    }
+    for (const element of [document.documentElement, document.body]) {
+        if (element !== null) {
+            element.style.removeProperty('overflow');
+        }
+    }
+    return removed;
+})"#;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ConsentAction {
+    Accept,
+    Reject,
+    // only remove the overlay, leaving consent undecided
+    Remove,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ConsentRule {
+    pub name: String,
+    // present if the consent platform is used on the page
+    pub detect: String,
+    pub accept: Option<String>,
+    pub reject: Option<String>,
+    // banner, backdrop and iframes of the platform
+    pub remove: Vec<String>,
+}
+
+// Gets consent banners and walls out of the way before a page is captured.
+// Detected platforms get their accept or reject button clicked, whatever of
+// the overlay is left afterwards is removed.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ConsentHandler {
+    pub action: ConsentAction,
+    pub rules: Vec<ConsentRule>,
+    // time for the page to react to the click
+    pub delay: Duration,
+}
+
+impl Default for ConsentHandler {
+    fn default() -> Self {
+        let rules = CONSENT_RULES
+            .iter()
+            .map(|(name, detect, accept, reject, remove)| ConsentRule {
+                name: name.to_string(),
+                detect: detect.to_string(),
+                accept: Some(accept.to_string()).filter(|xpath| !xpath.is_empty()),
+                reject: Some(reject.to_string()).filter(|xpath| !xpath.is_empty()),
+                remove: remove.iter().map(|xpath| xpath.to_string()).collect(),
+            })
+            .collect();
+
+        Self {
+            action: ConsentAction::Reject,
+            rules,
+            delay: Duration::from_millis(500),
+        }
+    }
+}
+
+impl ConsentHandler {
+    pub fn new(action: ConsentAction) -> Self {
+        Self {
+            action,
+            ..Self::default()
+        }
+    }
+
+    // Names of the rules matching a static document.
+    pub fn detect(&self, document: &Document) -> Result<Vec<&str>, FullTextParserError> {
+        let context = xpath_context(document)?;
+        let mut detected = Vec::new();
+        for rule in &self.rules {
+            if !Util::evaluate_xpath(&context, &rule.detect, false)?.is_empty() {
+                detected.push(rule.name.as_str());
+            }
+        }
+        Ok(detected)
+    }
+
+    // Remove the overlays of all detected platforms from a static document.
+    // Returns the names of the removed platforms.
+    pub fn remove(&self, document: &Document) -> Result<Vec<&str>, FullTextParserError> {
+        let detected = self.detect(document)?;
+        let context = xpath_context(document)?;
+        for rule in self
+            .rules
+            .iter()
+            .filter(|rule| detected.contains(&rule.name.as_str()))
+        {
+            for xpath in &rule.remove {
+                Util::strip_node(&context, xpath, "consent", &mut Trace::default())?;
+            }
+        }
+        Ok(detected)
+    }
+
+    // Deal with the consent platforms on the page loaded in `tab`.
+    pub(crate) fn handle(&self, tab: &Tab) -> anyhow::Result<()> {
+        for rule in &self.rules {
+            if !exists(tab, &rule.detect)? {
+                continue;
+            }
+
+            let button = match self.action {
+                ConsentAction::Accept => rule.accept.as_ref(),
+                ConsentAction::Reject => rule.reject.as_ref(),
+                ConsentAction::Remove => None,
+            };
+            if let Some(button) = button {
+                let clicked = evaluate(tab, &format!("{CLICK_SCRIPT}({})", json!(button)))?;
+                if clicked == Value::Bool(true) {
+                    thread::sleep(self.delay);
+                }
+            }
+
+            evaluate(tab, &format!("{REMOVE_SCRIPT}({})", json!(rule.remove)))?;
+            log::debug!("Handled consent platform {}", rule.name);
+        }
+        Ok(())
+    }
+}
+
+fn xpath_context(document: &Document) -> Result<Context, FullTextParserError> {
+    Context::new(document).map_err(|()| {
+        log::error!("Creating xpath context failed for consent check");
+        FullTextParserError::Xml
+    })
+}
+
+fn exists(tab: &Tab, xpath: &str) -> anyhow::Result<bool> {
+    let expression = format!(
+        "document.evaluate({}, document, null, XPathResult.FIRST_ORDERED_NODE_TYPE, null).singleNodeValue !== null",
+        json!(xpath)
+    );
+    Ok(evaluate(tab, &expression)? == Value::Bool(true))
+}
+
+fn evaluate(tab: &Tab, expression: &str) -> anyhow::Result<Value> {
+    Ok(tab
+        .evaluate(expression, false)?
+        .value
+        .unwrap_or(Value::Null))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use url::Url;
+
+    use super::{ConsentAction, ConsentHandler};
+    use crate::readability::encoding::HtmlEncoding;
+    use crate::readability::{BrowserSession, RenderOptions, SessionOptions};
+
+    #[test]
+    fn remove_consent_overlays() {
+        let fixtures = [
+            (
+                include_str!("../../../resources/tests/consent/onetrust.html"),
+                vec!["onetrust"],
+                "cookies to personalise",
+            ),
+            (
+                include_str!("../../../resources/tests/consent/sourcepoint.html"),
+                vec!["sourcepoint"],
+                "sp_message_iframe",
+            ),
+            (
+                include_str!("../../../resources/tests/consent/cookie_banner.html"),
+                vec!["cookie_banner"],
+                "improve your experience",
+            ),
+            (
+                include_str!("../../../resources/tests/consent/no_consent.html"),
+                vec![],
+                "",
+            ),
+        ];
+
+        let handler = ConsentHandler::default();
+        for (html, platforms, banner) in fixtures {
+            let document = HtmlEncoding::parse_utf8(html).unwrap();
+            assert_eq!(handler.detect(&document).unwrap(), platforms);
+            assert_eq!(handler.remove(&document).unwrap(), platforms);
+
+            let html = document.to_string();
+            assert!(banner.is_empty() || !html.contains(banner));
+            assert!(handler.detect(&document).unwrap().is_empty());
+            // the article itself stays
+            assert!(html.contains("<h1>"));
+            assert!(html.contains("<body"));
+        }
+
+        // class names that only contain a banner name aren't banners
+        let html = include_str!("../../../resources/tests/consent/cookie_banner.html");
+        let document = HtmlEncoding::parse_utf8(html).unwrap();
+        handler.remove(&document).unwrap();
+        let html = document.to_string();
+        assert!(html.contains(r#"<body class="cookie-consent-pending">"#));
+        assert!(html.contains(r#"<main class="has-cookie-notice">"#));
+        assert!(html.contains("fan oven"));
+
+        let document = HtmlEncoding::parse_utf8(
+            r#"<html><body><div id="truste-consent-track"></div>
+            <div class="truste_overlay"></div><div class="truste_box_overlay x"></div>
+            <div class="no-truste_overlay-here"><h1>Title</h1></div></body></html>"#,
+        )
+        .unwrap();
+        assert_eq!(handler.remove(&document).unwrap(), vec!["trustarc"]);
+        let html = document.to_string();
+        assert!(!html.contains(r#"class="truste_overlay""#));
+        assert!(!html.contains("truste_box_overlay x"));
+        assert!(html.contains("<h1>Title</h1>"));
+    }
+
+    // needs Chrome, run with `cargo test -- --ignored`
+    #[test]
+    #[ignore]
+    fn handle_consent_in_browser() {
+        let fixtures = [
+            (
+                "onetrust.html",
+                ConsentAction::Reject,
+                "onetrust-consent-sdk",
+                Some("reject"),
+            ),
+            (
+                "onetrust.html",
+                ConsentAction::Accept,
+                "onetrust-consent-sdk",
+                Some("accept"),
+            ),
+            (
+                "onetrust.html",
+                ConsentAction::Remove,
+                "onetrust-consent-sdk",
+                None,
+            ),
+            (
+                "sourcepoint.html",
+                ConsentAction::Reject,
+                "sp_message_container",
+                None,
+            ),
+            (
+                "cookie_banner.html",
+                ConsentAction::Accept,
+                "improve your experience",
+                None,
+            ),
+        ];
+
+        let session = BrowserSession::new(SessionOptions::default()).unwrap();
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("resources/tests/consent");
+        for (fixture, action, banner, clicked) in fixtures {
+            let url = Url::from_file_path(dir.join(fixture)).unwrap();
+            let options = RenderOptions {
+                consent: Some(ConsentHandler::new(action)),
+                ..RenderOptions::default()
+            };
+            let html = session.html(url.as_str(), &options).unwrap();
+
+            assert!(!html.contains(banner), "{fixture}: banner left");
+            // the buttons record which one was clicked on <html>
+            match clicked {
+                Some(button) => assert!(
+                    html.contains(&format!(r#"data-consent="{button}""#)),
+                    "{fixture}: {button} not clicked"
+                ),
+                None => assert!(!html.contains("data-consent="), "{fixture}: clicked"),
+            }
+            assert!(html.contains("<h1>"), "{fixture}: article removed");
+        }
+    }
+}
@@ -36,6 +36,30 @@ pub static BYLINE: Lazy<Regex> = Lazy::new(|| {
         .build()
         .expect("BYLINE regex")
 });
+pub static BYLINE_PREFIX: Lazy<Regex> = Lazy::new(|| {
+    RegexBuilder::new(r#"^\s*(written\s+|posted\s+|words\s+)?by\b[:\s]*"#)
+        .case_insensitive(true)
+        .build()
+        .expect("BYLINE_PREFIX regex")
+});
+pub static BYLINE_SEPARATOR: Lazy<Regex> = Lazy::new(|| {
+    RegexBuilder::new(r#"\s*(?:,|;|&|\||\band\b|\bund\b)\s*"#)
+        .case_insensitive(true)
+        .build()
+        .expect("BYLINE_SEPARATOR regex")
+});
+pub static DATE_TEXT: Lazy<Regex> = Lazy::new(|| {
+    RegexBuilder::new(r#"\d{4}-\d{2}-\d{2}|\d{1,2}(?:st|nd|rd|th)?\.?\s+(?:jan|feb|mar|apr|may|jun|jul|aug|sep|oct|nov|dec)[a-z]*\.?,?\s+\d{4}|(?:jan|feb|mar|apr|may|jun|jul|aug|sep|oct|nov|dec)[a-z]*\.?\s+\d{1,2}(?:st|nd|rd|th)?,?\s+\d{4}"#)
+        .case_insensitive(true)
+        .build()
+        .expect("DATE_TEXT regex")
+});
+pub static DATE_ORDINAL: Lazy<Regex> = Lazy::new(|| {
+    RegexBuilder::new(r#"(\d)(?:st|nd|rd|th)\b|[.,]"#)
+        .case_insensitive(true)
+        .build()
+        .expect("DATE_ORDINAL regex")
+});
 pub static NORMALIZE: Lazy<Regex> = Lazy::new(|| Regex::new(r#"\s{2,}"#).expect("NORMALIZE regex"));
 pub static TOKENIZE: Lazy<Regex> = Lazy::new(|| Regex::new(r#"\W+"#).expect("TOKENIZE regex"));
 pub static UNLIELY_CANDIDATES: Lazy<Regex> = Lazy::new(|| {
@@ -97,7 +121,16 @@ pub static BASE64_DATA_URL: Lazy<Regex> = Lazy::new(|| {
 });
 pub const SCORE_ATTR: &str = "content_score";
 pub const DATA_TABLE_ATTR: &str = "is_data_table";
+pub const NODE_ID_ATTR: &str = "readability_node";
 pub const MINIMUM_TOPCANDIDATES: usize = 3;
+pub const DEFAULT_TOP_CANDIDATES: usize = 5;
+pub const CLASS_WEIGHT: i64 = 25;
+pub const EXCERPT_MIN_LENGTH: usize = 80;
+pub const EXCERPT_MAX_LENGTH: usize = 300;
+pub const DEFAULT_MAX_PAGES: usize = 10;
+pub const MIN_LEAD_IMAGE_SIZE: u32 = 150;
+pub const WORDS_PER_MINUTE: usize = 230;
+pub const CJK_CHARS_PER_MINUTE: usize = 500;
 pub const UNLIKELY_ROLES: &[&str] = &[
     "menu",
     "menubar",
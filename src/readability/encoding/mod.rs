@@ -0,0 +1,340 @@
+use std::borrow::Cow;
+use std::ffi::CStr;
+use std::os::raw::c_char;
+use std::ptr;
+
+use encoding_rs::{
+    Encoding, BIG5, EUC_JP, EUC_KR, GBK, KOI8_R, SHIFT_JIS, UTF_8, WINDOWS_1250, WINDOWS_1251,
+    WINDOWS_1252, WINDOWS_1253, WINDOWS_1254, WINDOWS_1255, WINDOWS_1256,
+};
+use libxml::bindings::{
+    htmlParserOption_HTML_PARSE_NOERROR, htmlParserOption_HTML_PARSE_NOWARNING,
+    htmlParserOption_HTML_PARSE_RECOVER, htmlReadMemory,
+};
+use libxml::tree::Document;
+use once_cell::sync::Lazy;
+use regex::bytes::{Regex, RegexBuilder};
+
+use super::FullTextParserError;
+
+const UTF_8_LABEL: &CStr = c"utf-8";
+// how much of the document is searched for a <meta> charset declaration
+const META_PRESCAN_LENGTH: usize = 4096;
+
+static CONTENT_TYPE_CHARSET: Lazy<Regex> = Lazy::new(|| {
+    RegexBuilder::new(r#"charset\s*=\s*["']?\s*([\w\-:.]+)"#)
+        .case_insensitive(true)
+        .build()
+        .expect("CONTENT_TYPE_CHARSET regex")
+});
+// covers both <meta charset="..."> and
+// <meta http-equiv="Content-Type" content="text/html; charset=...">
+static META_CHARSET: Lazy<Regex> = Lazy::new(|| {
+    RegexBuilder::new(r#"(<meta\s[^>]*?charset\s*=\s*["']?\s*)([\w\-:.]+)"#)
+        .case_insensitive(true)
+        .build()
+        .expect("META_CHARSET regex")
+});
+
+// most frequent characters of written Chinese (simplified and traditional) and
+// Korean. Decoding with the wrong CJK encoding produces valid but random
+// characters, so these are what tells GBK, Big5 and EUC-KR apart.
+const COMMON_HAN: &str = "的一是不了在人有我他这個个们們中来來上大为為和国國地到以说說时時要就出会會可也你对對生能而子那得于於着著下自之年过過发發后後作里裡用道行所然家种種事成方多经經么麼去法学學如都同现現当當没沒动動面起看定天分还還进進好小部其些主样樣理心她本前开開但因只从從想实實日";
+const COMMON_HANGUL: &str = "이다는의에을를가고하한지서로기도있사어게나수시리자그들것아인으정대보면해적일만원부와과요니국서한전했습";
+
+#[derive(Clone, Copy, PartialEq)]
+enum Script {
+    Latin,
+    Greek,
+    Cyrillic,
+    Hebrew,
+    Arabic,
+    Japanese,
+    Chinese,
+    Korean,
+}
+
+// candidates of the statistical fallback, in order of preference on a tie
+const FALLBACK_ENCODINGS: &[(&Encoding, Script)] = &[
+    (WINDOWS_1252, Script::Latin),
+    (WINDOWS_1250, Script::Latin),
+    (WINDOWS_1254, Script::Latin),
+    (WINDOWS_1255, Script::Hebrew),
+    (WINDOWS_1253, Script::Greek),
+    (WINDOWS_1256, Script::Arabic),
+    (WINDOWS_1251, Script::Cyrillic),
+    (KOI8_R, Script::Cyrillic),
+    (SHIFT_JIS, Script::Japanese),
+    (EUC_JP, Script::Japanese),
+    (GBK, Script::Chinese),
+    (BIG5, Script::Chinese),
+    (EUC_KR, Script::Korean),
+];
+
+pub struct HtmlEncoding;
+
+impl HtmlEncoding {
+    // Pick the encoding of a html document. In order of precedence: byte order
+    // mark, charset of the HTTP Content-Type header, <meta> declaration and
+    // finally a guess based on which encoding decodes the text most plausibly.
+    pub fn detect(bytes: &[u8], content_type: Option<&str>) -> &'static Encoding {
+        if let Some((encoding, _)) = Encoding::for_bom(bytes) {
+            return encoding;
+        }
+
+        if let Some(encoding) = content_type.and_then(|content_type| {
+            Self::charset_label(&CONTENT_TYPE_CHARSET, content_type.as_bytes())
+        }) {
+            return encoding;
+        }
+
+        let prescan = &bytes[..bytes.len().min(META_PRESCAN_LENGTH)];
+        if let Some(encoding) = Self::charset_label(&META_CHARSET, prescan) {
+            // a document that can be read as ascii can't be utf-16 encoded
+            if encoding.output_encoding() != encoding {
+                return UTF_8;
+            }
+            return encoding;
+        }
+
+        Self::guess(bytes)
+    }
+
+    // Decode a html document to utf-8 using the encoding found by `detect`.
+    pub fn decode(bytes: &[u8], content_type: Option<&str>) -> String {
+        let encoding = Self::detect(bytes, content_type);
+        log::debug!("Decoding html as {}", encoding.name());
+
+        let (html, _, had_errors) = encoding.decode(bytes);
+        if had_errors {
+            log::warn!(
+                "Html contains bytes that are invalid in {}",
+                encoding.name()
+            );
+        }
+        html.into_owned()
+    }
+
+    // Point <meta> charset declarations of already decoded html to utf-8. libxml
+    // honors them even when told that the input is utf-8 and would decode the
+    // text a second time.
+    pub fn declare_utf8(html: &str) -> Cow<'_, str> {
+        match META_CHARSET.replace_all(html.as_bytes(), &b"${1}utf-8"[..]) {
+            Cow::Borrowed(_) => Cow::Borrowed(html),
+            Cow::Owned(html) => Cow::Owned(String::from_utf8_lossy(&html).into_owned()),
+        }
+    }
+
+    // Parse already decoded html. libxml's `parse_string_with_encoding` hands
+    // a pointer to a dropped `CString` to libxml2 as the name of the encoding,
+    // which under load ends up naming a random codec and garbles the text.
+    pub fn parse_utf8(html: &str) -> Result<Document, FullTextParserError> {
+        let html = Self::declare_utf8(html);
+        let length = i32::try_from(html.len()).map_err(|_| FullTextParserError::Xml)?;
+        let options = htmlParserOption_HTML_PARSE_RECOVER
+            | htmlParserOption_HTML_PARSE_NOERROR
+            | htmlParserOption_HTML_PARSE_NOWARNING;
+
+        let document = unsafe {
+            htmlReadMemory(
+                html.as_ptr() as *const c_char,
+                length,
+                ptr::null(),
+                UTF_8_LABEL.as_ptr(),
+                options as i32,
+            )
+        };
+        if document.is_null() {
+            log::error!("Parsing HTML failed");
+            return Err(FullTextParserError::Xml);
+        }
+        Ok(Document::new_ptr(document))
+    }
+
+    fn charset_label(regex: &Regex, haystack: &[u8]) -> Option<&'static Encoding> {
+        let captures = regex.captures(haystack)?;
+        let label = captures.get(captures.len() - 1)?.as_bytes();
+        let encoding = Encoding::for_label(label);
+        if encoding.is_none() {
+            log::debug!("Unknown charset '{}'", String::from_utf8_lossy(label));
+        }
+        encoding
+    }
+
+    fn guess(bytes: &[u8]) -> &'static Encoding {
+        if bytes.is_ascii() || std::str::from_utf8(bytes).is_ok() {
+            return UTF_8;
+        }
+
+        let mut best = (WINDOWS_1252, i64::MIN);
+        for (encoding, script) in FALLBACK_ENCODINGS {
+            let Some(text) = encoding.decode_without_bom_handling_and_without_replacement(bytes)
+            else {
+                continue;
+            };
+
+            let score = Self::score(&text, *script, encoding.is_single_byte());
+            if score > best.1 {
+                best = (encoding, score);
+            }
+        }
+
+        log::debug!("Guessed encoding {} (score {})", best.0.name(), best.1);
+        best.0
+    }
+
+    // Rate how much `text` looks like a sensible text in `script`. Characters of the
+    // expected script add to the score, control characters, symbols and mixed up
+    // scripts within a word subtract from it.
+    fn score(text: &str, script: Script, single_byte: bool) -> i64 {
+        // multi byte encodings produce one character for two bytes
+        let weight = if single_byte { 1 } else { 2 };
+        let mut score = 0;
+        let mut previous = ' ';
+
+        for c in text.chars() {
+            if c.is_ascii() {
+                previous = c;
+                continue;
+            }
+
+            score += match Self::script_of(c) {
+                _ if c.is_control() || ('\u{e000}'..='\u{f8ff}').contains(&c) => -10,
+                // half-width katakana are rare and a typical result of
+                // decoding chinese text as Shift_JIS
+                _ if ('\u{ff61}'..='\u{ff9f}').contains(&c) => -3,
+                Some(Script::Latin) if script == Script::Latin => {
+                    // accented letters usually sit between ascii letters
+                    if previous.is_ascii() {
+                        1
+                    } else {
+                        -1
+                    }
+                }
+                // other scripts don't share words with latin letters
+                Some(_) if previous.is_ascii_alphabetic() => -1,
+                Some(Script::Cyrillic) if script == Script::Cyrillic => {
+                    // capitals in the middle of a word hint at KOI8-R vs windows-1251 mixups
+                    if c.is_uppercase() && previous.is_lowercase() {
+                        -2
+                    } else {
+                        1
+                    }
+                }
+                // japanese mixes kana with the same ideographs as chinese
+                Some(found)
+                    if found == script
+                        || script == Script::Japanese && found == Script::Chinese =>
+                {
+                    if script == Script::Japanese && ('\u{3040}'..='\u{30ff}').contains(&c)
+                        || COMMON_HAN.contains(c) && script == Script::Chinese
+                        || COMMON_HANGUL.contains(c) && script == Script::Korean
+                    {
+                        3 * weight
+                    } else {
+                        weight
+                    }
+                }
+                Some(_) => -1,
+                None => 0,
+            };
+            previous = c;
+        }
+
+        score
+    }
+
+    fn script_of(c: char) -> Option<Script> {
+        match c {
+            '\u{c0}'..='\u{24f}' if c.is_alphabetic() => Some(Script::Latin),
+            '\u{370}'..='\u{3ff}' => Some(Script::Greek),
+            '\u{400}'..='\u{4ff}' => Some(Script::Cyrillic),
+            '\u{590}'..='\u{5ff}' => Some(Script::Hebrew),
+            '\u{600}'..='\u{6ff}' => Some(Script::Arabic),
+            '\u{3040}'..='\u{30ff}' => Some(Script::Japanese),
+            '\u{4e00}'..='\u{9fff}' => Some(Script::Chinese),
+            '\u{ac00}'..='\u{d7af}' => Some(Script::Korean),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use encoding_rs::{
+        Encoding, EUC_KR, GBK, SHIFT_JIS, UTF_8, WINDOWS_1250, WINDOWS_1251, WINDOWS_1252,
+    };
+
+    use super::HtmlEncoding;
+
+    fn html(encoding: &'static Encoding, text: &str) -> Vec<u8> {
+        let html =
+            format!("<html><head><title>Test</title></head><body><p>{text}</p></body></html>");
+        encoding.encode(&html).0.into_owned()
+    }
+
+    #[test]
+    fn declared_encoding() {
+        let bytes = html(WINDOWS_1251, "Привет");
+        let detected = HtmlEncoding::detect(&bytes, Some("text/html; charset=windows-1251"));
+        assert_eq!(detected, WINDOWS_1251);
+
+        let bytes = [
+            b"<html><head><meta charset=\"Shift_JIS\">".as_slice(),
+            &html(SHIFT_JIS, "日本語"),
+        ]
+        .concat();
+        assert_eq!(HtmlEncoding::detect(&bytes, Some("text/html")), SHIFT_JIS);
+
+        let bytes = b"<meta http-equiv='Content-Type' content='text/html; charset=gb2312'>";
+        assert_eq!(HtmlEncoding::detect(bytes, None), GBK);
+        assert_eq!(
+            HtmlEncoding::declare_utf8(std::str::from_utf8(bytes).unwrap()),
+            "<meta http-equiv='Content-Type' content='text/html; charset=utf-8'>"
+        );
+
+        let bytes = [b"\xEF\xBB\xBF".as_slice(), &html(UTF_8, "日本語")].concat();
+        assert_eq!(
+            HtmlEncoding::detect(&bytes, Some("text/html; charset=iso-8859-1")),
+            UTF_8
+        );
+        assert!(HtmlEncoding::decode(&bytes, None).contains("日本語"));
+    }
+
+    #[test]
+    fn guess_encoding() {
+        let samples = [
+            (UTF_8, "Größere Änderungen übernehmen wir später."),
+            (
+                WINDOWS_1252,
+                "Les élèves ont été très déçus par la décision du ministère.",
+            ),
+            (
+                WINDOWS_1250,
+                "Zażółć gęślą jaźń. Łódź i Kraków są pięknymi miastami.",
+            ),
+            (
+                WINDOWS_1251,
+                "Съешь же ещё этих мягких французских булок, да выпей чаю.",
+            ),
+            (
+                SHIFT_JIS,
+                "これは日本語の文章です。今日はとても良い天気ですね。",
+            ),
+            (
+                GBK,
+                "我们今天在这里讨论中国的经济发展和社会问题，这是一个重要的话题。",
+            ),
+            (
+                EUC_KR,
+                "안녕하세요. 오늘은 날씨가 정말 좋습니다. 우리는 한국어를 공부하고 있습니다.",
+            ),
+        ];
+
+        for (encoding, text) in samples {
+            let bytes = html(encoding, text);
+            assert_eq!(HtmlEncoding::detect(&bytes, None), encoding, "{text}");
+            assert!(HtmlEncoding::decode(&bytes, None).contains(text));
+        }
+    }
+}
@@ -0,0 +1,190 @@
+use std::thread;
+use std::time::Duration;
+
+use headless_chrome::browser::tab::Tab;
+use serde_json::Value;
+
+// elements that commonly hide the rest of an article
+const EXPAND_SELECTORS: &[&str] = &[
+    "details:not([open]) > summary",
+    "button.read-more",
+    "button.show-more",
+    ".read-more-button",
+    ".show-more-button",
+    "[data-testid='read-more']",
+];
+
+// Clicks every visible element matching the selectors once. Links that would
+// navigate away from the page are left alone. Clicked elements are remembered
+// outside of the DOM, so the captured html stays unchanged.
+const CLICK_SCRIPT: &str = r#"(selectors => {
+    const expanded = window.__readahExpanded = window.__readahExpanded || new WeakSet();
+    let clicked = 0;
+    for (const element of document.querySelectorAll(selectors)) {
+        if (expanded.has(element) || element.offsetParent === null) {
+            continue;
+        }
+        const link = element.closest('a[href]');
+        const href = link ? link.getAttribute('href') : '';
+        if (link && !href.startsWith('#') && !href.startsWith('javascript:')) {
+            continue;
+        }
+        expanded.add(element);
+        element.click();
+        clicked += 1;
+    }
+    return clicked;
+})"#;
+
+// Scrolls one screen down, answers whether the bottom of the page is reached.
+const SCROLL_SCRIPT: &str = "window.scrollBy(0, window.innerHeight); \
+    window.innerHeight + window.scrollY >= document.documentElement.scrollHeight - 1";
+
+// Steps run before a page is captured to load content that only appears on
+// scrolling or behind "Read more" buttons. Lazy images get their real URL
+// when they are scrolled into view.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ExpandOptions {
+    // scroll down until the page stops growing
+    pub scroll: bool,
+    // scroll steps at most, pages with infinite scrolling never stop growing
+    pub max_scrolls: usize,
+    // time given to content to load after every scroll step and click
+    pub delay: Duration,
+    // CSS selectors of elements to click
+    pub selectors: Vec<String>,
+    // expanded content can contain further buttons to click
+    pub max_click_rounds: usize,
+}
+
+impl Default for ExpandOptions {
+    fn default() -> Self {
+        Self {
+            scroll: true,
+            max_scrolls: 30,
+            delay: Duration::from_millis(300),
+            selectors: EXPAND_SELECTORS.iter().map(|s| s.to_string()).collect(),
+            max_click_rounds: 3,
+        }
+    }
+}
+
+impl ExpandOptions {
+    pub(crate) fn expand(&self, tab: &Tab) -> anyhow::Result<()> {
+        let mut clicked = self.click(tab)?;
+        if self.scroll {
+            self.scroll(tab)?;
+            // some buttons are only added once their part of the page is reached
+            clicked += self.click(tab)?;
+        }
+        log::debug!("Expanded page with {clicked} clicks");
+        Ok(())
+    }
+
+    fn click(&self, tab: &Tab) -> anyhow::Result<u64> {
+        let mut clicked = 0;
+        if self.selectors.is_empty() {
+            return Ok(clicked);
+        }
+
+        let script = self.click_script();
+        for _ in 0..self.max_click_rounds {
+            let count = evaluate(tab, &script)?.as_u64().unwrap_or(0);
+            if count == 0 {
+                break;
+            }
+            clicked += count;
+            thread::sleep(self.delay);
+        }
+        Ok(clicked)
+    }
+
+    fn scroll(&self, tab: &Tab) -> anyhow::Result<()> {
+        let mut height = page_height(tab)?;
+        for step in 0..self.max_scrolls {
+            let at_bottom = evaluate(tab, SCROLL_SCRIPT)?.as_bool().unwrap_or(true);
+            thread::sleep(self.delay);
+
+            let new_height = page_height(tab)?;
+            if at_bottom && new_height <= height {
+                log::debug!("Page stopped growing after {} scroll steps", step + 1);
+                break;
+            }
+            height = new_height;
+        }
+
+        evaluate(tab, "window.scrollTo(0, 0)")?;
+        Ok(())
+    }
+
+    fn click_script(&self) -> String {
+        let selectors = Value::String(self.selectors.join(", "));
+        format!("{CLICK_SCRIPT}({selectors})")
+    }
+}
+
+fn page_height(tab: &Tab) -> anyhow::Result<u64> {
+    Ok(evaluate(tab, "document.documentElement.scrollHeight")?
+        .as_u64()
+        .unwrap_or(0))
+}
+
+fn evaluate(tab: &Tab, expression: &str) -> anyhow::Result<Value> {
+    Ok(tab
+        .evaluate(expression, false)?
+        .value
+        .unwrap_or(Value::Null))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use url::Url;
+
+    use super::ExpandOptions;
+    use crate::readability::{BrowserSession, RenderOptions, SessionOptions};
+
+    #[test]
+    fn click_script() {
+        let options = ExpandOptions {
+            selectors: vec!["button[aria-label=\"Read more\"]".into(), ".more".into()],
+            ..ExpandOptions::default()
+        };
+        let script = options.click_script();
+        assert!(script.starts_with("(selectors => {"));
+        assert!(script.ends_with(r#"})("button[aria-label=\"Read more\"], .more")"#));
+    }
+
+    // needs Chrome, run with `cargo test -- --ignored`
+    #[test]
+    #[ignore]
+    fn expand_in_browser() {
+        let session = BrowserSession::new(SessionOptions::default()).unwrap();
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("resources/tests/expand/lazy.html");
+        let url = Url::from_file_path(path).unwrap();
+        let options = RenderOptions {
+            expand: Some(ExpandOptions::default()),
+            ..RenderOptions::default()
+        };
+        let html = session.html(url.as_str(), &options).unwrap();
+
+        assert!(html.contains("<details open"), "details not opened");
+        assert!(!html.contains("readah"), "click markers left in the page");
+        // the button only shows up after scrolling
+        assert!(
+            html.contains("climbs through the forest"),
+            "read more not clicked"
+        );
+
+        let options = RenderOptions {
+            expand: Some(ExpandOptions {
+                scroll: false,
+                ..ExpandOptions::default()
+            }),
+            ..RenderOptions::default()
+        };
+        let html = session.html(url.as_str(), &options).unwrap();
+        assert!(!html.contains("climbs through the forest"));
+    }
+}
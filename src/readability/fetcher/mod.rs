@@ -0,0 +1,221 @@
+use reqwest::{header::CONTENT_TYPE, Client, Response, StatusCode};
+use thiserror::Error;
+use url::Url;
+
+use super::encoding::HtmlEncoding;
+use super::util::Util;
+use super::ConfigEntry;
+
+#[derive(Error, Debug)]
+pub enum FetchError {
+    #[error("Request failed: {0}")]
+    Request(#[from] reqwest::Error),
+    #[error("Server responded with status {0}")]
+    Status(StatusCode),
+    #[error("Content-type suggest no html")]
+    ContentType,
+    #[error("Invalid http_header in site config")]
+    Header,
+}
+
+// The downloaded html of a page together with the URL it was served from.
+// After redirects `url` differs from the requested URL and should be used
+// as base URL of the article.
+#[derive(Clone, Debug)]
+pub struct FetchedPage {
+    pub url: Url,
+    pub html: String,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Fetcher {
+    client: Client,
+}
+
+impl Fetcher {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Use a preconfigured client, e.g. with a proxy, timeouts or a custom
+    // redirect policy. Redirects are only reflected in the result if the
+    // client follows them.
+    pub fn with_client(client: Client) -> Self {
+        Self { client }
+    }
+
+    pub fn client(&self) -> &Client {
+        &self.client
+    }
+
+    pub async fn fetch(
+        &self,
+        url: &Url,
+        config: Option<&ConfigEntry>,
+        global_config: &ConfigEntry,
+    ) -> Result<FetchedPage, FetchError> {
+        let headers =
+            Util::generate_headers(config, global_config).map_err(|_| FetchError::Header)?;
+        let response = self
+            .client
+            .get(url.as_str())
+            .headers(headers)
+            .send()
+            .await
+            .map_err(|error| {
+                log::error!("Downloading '{url}' failed: {error}");
+                FetchError::Request(error)
+            })?;
+
+        Self::read_page(url, response).await
+    }
+
+    // Check the response of a request for `url` and decode its body.
+    async fn read_page(url: &Url, response: Response) -> Result<FetchedPage, FetchError> {
+        if !response.status().is_success() {
+            log::error!("Downloading '{url}' failed: {}", response.status());
+            return Err(FetchError::Status(response.status()));
+        }
+
+        if !Util::check_content_type(&response).unwrap_or(false) {
+            return Err(FetchError::ContentType);
+        }
+
+        let final_url = Util::check_redirect(&response, url).unwrap_or_else(|| url.clone());
+        let content_type = response
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .map(String::from);
+        let bytes = response.bytes().await?;
+        let html = HtmlEncoding::decode(&bytes, content_type.as_deref());
+
+        Ok(FetchedPage {
+            url: final_url,
+            html,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use reqwest::{header::CONTENT_TYPE, ResponseBuilderExt, StatusCode};
+    use url::Url;
+
+    use super::{FetchError, Fetcher};
+    use crate::readability::util::Util;
+    use crate::readability::{ConfigEntry, Header};
+
+    fn response(
+        status: u16,
+        content_type: Option<&str>,
+        url: &str,
+        body: &[u8],
+    ) -> reqwest::Response {
+        let mut builder = http::Response::builder()
+            .status(status)
+            .url(Url::parse(url).unwrap());
+        if let Some(content_type) = content_type {
+            builder = builder.header(CONTENT_TYPE, content_type);
+        }
+        builder.body(body.to_vec()).unwrap().into()
+    }
+
+    #[tokio::test]
+    async fn read_page() {
+        let url = Url::parse("https://example.com/a").unwrap();
+
+        // latin-1 body, charset named by the header
+        let body = b"<html><body><p>Caf\xe9 cr\xe8me</p></body></html>";
+        let page = Fetcher::read_page(
+            &url,
+            response(
+                200,
+                Some("text/html; charset=ISO-8859-1"),
+                url.as_str(),
+                body,
+            ),
+        )
+        .await
+        .unwrap();
+        assert_eq!(page.url, url);
+        assert!(page.html.contains("Café crème"));
+
+        // redirects show up as the url of the page
+        let page = Fetcher::read_page(
+            &url,
+            response(
+                200,
+                Some("text/html"),
+                "https://www.example.com/b",
+                b"<p>moved</p>",
+            ),
+        )
+        .await
+        .unwrap();
+        assert_eq!(page.url.as_str(), "https://www.example.com/b");
+
+        let error = Fetcher::read_page(
+            &url,
+            response(200, Some("application/pdf"), url.as_str(), b"%PDF"),
+        )
+        .await
+        .unwrap_err();
+        assert!(matches!(error, FetchError::ContentType));
+        let error = Fetcher::read_page(&url, response(200, None, url.as_str(), b""))
+            .await
+            .unwrap_err();
+        assert!(matches!(error, FetchError::ContentType));
+        let error = Fetcher::read_page(&url, response(404, Some("text/html"), url.as_str(), b""))
+            .await
+            .unwrap_err();
+        assert!(matches!(error, FetchError::Status(StatusCode::NOT_FOUND)));
+    }
+
+    #[test]
+    fn site_headers() {
+        let header = |name: &str, value: &str| Header {
+            name: name.into(),
+            value: value.into(),
+        };
+        let site = ConfigEntry {
+            header: vec![header("User-Agent", "site agent"), header("Cookie", "a=1")],
+            ..ConfigEntry::default()
+        };
+        let global = ConfigEntry {
+            header: vec![header("Referer", "https://example.com/")],
+            ..ConfigEntry::default()
+        };
+
+        let headers = Util::generate_headers(Some(&site), &global).unwrap();
+        assert_eq!(headers["user-agent"], "site agent");
+        assert_eq!(headers["cookie"], "a=1");
+        assert_eq!(headers["referer"], "https://example.com/");
+        assert!(Util::generate_headers(None, &global)
+            .unwrap()
+            .get("cookie")
+            .is_none());
+
+        // a site header replaces the global header of the same name
+        let global = ConfigEntry {
+            header: vec![
+                header("User-Agent", "global agent"),
+                header("Cookie", "b=2"),
+            ],
+            ..ConfigEntry::default()
+        };
+        let headers = Util::generate_headers(Some(&site), &global).unwrap();
+        assert_eq!(headers["user-agent"], "site agent");
+        assert_eq!(headers["cookie"], "a=1");
+        assert_eq!(
+            Util::generate_headers(None, &global).unwrap()["user-agent"],
+            "global agent"
+        );
+
+        let invalid = ConfigEntry {
+            header: vec![header("Bad Header", "x")],
+            ..ConfigEntry::default()
+        };
+        assert!(Util::generate_headers(Some(&invalid), &global).is_err());
+    }
+}
@@ -1,49 +1,57 @@
-use headless_chrome::{types::PrintToPdfOptions, Browser, LaunchOptions};
+use std::sync::Arc;
+
+use crate::readability::{
+    BrowserSession, Capture, ExtractOptions, Readability, RenderOptions, SessionOptions, TextStats,
+};
 use html2text;
 use pdfium_render::prelude::*;
-use crate::readability::Readability;
 use url::Url;
 
-
+// every call launches its own browser, batch jobs should keep a `BrowserSession`
 pub async fn get_webpage_text_headless(url: &str) -> anyhow::Result<String> {
-    // set the headless Chrome to open a webpage in portrait mode of certain width and height
-    // here in an iPad resolution, is a way to pursuade webserver to send less non-essential
-    // data, and make the virtual browser to show the central content, for websites
-    // with responsive design, with less clutter
-    let options = LaunchOptions {
-        headless: true,
-        window_size: Some((820, 1180)),
-        ..Default::default()
-    };
-
-    let browser = Browser::new(options)?;
-
-    let tab = browser.new_tab()?;
-
-    tab.navigate_to(url)?;
-    tab.wait_until_navigated()?;
-
-    let pdf_options: Option<PrintToPdfOptions> = Some(PrintToPdfOptions {
-        landscape: Some(false),
-        display_header_footer: Some(false),
-        print_background: Some(false),
-        scale: Some(0.5),
-        paper_width: Some(11.0),
-        paper_height: Some(17.0),
-        margin_top: Some(0.1),
-        margin_bottom: Some(0.1),
-        margin_left: Some(0.1),
-        margin_right: Some(0.1),
-        // page_ranges: Some("1-2".to_string()),
-        ignore_invalid_page_ranges: Some(true),
-        prefer_css_page_size: Some(false),
-        transfer_mode: None,
-        ..Default::default()
-    });
-
-    let pdf_data = tab.print_to_pdf(pdf_options)?;
-
-    let pdf_as_vec = pdf_data.to_vec();
+    get_webpage_text_headless_with_options(url, &RenderOptions::default()).await
+}
+
+pub async fn get_webpage_text_headless_with_options(
+    url: &str,
+    options: &RenderOptions,
+) -> anyhow::Result<String> {
+    let (url, options) = (url.to_owned(), options.clone());
+    let pdf = blocking(move || BrowserSession::new(SessionOptions::default())?.pdf(&url, &options))
+        .await?;
+    pdf_to_text(pdf)
+}
+
+pub async fn get_html_headless(url: &str) -> anyhow::Result<String> {
+    get_html_headless_with_options(url, &RenderOptions::default()).await
+}
+
+pub async fn get_html_headless_with_options(
+    url: &str,
+    options: &RenderOptions,
+) -> anyhow::Result<String> {
+    let (url, options) = (url.to_owned(), options.clone());
+    blocking(move || BrowserSession::new(SessionOptions::default())?.html(&url, &options)).await
+}
+
+// Chrome is driven synchronously, keep it off the async worker threads.
+async fn blocking<T: Send + 'static>(
+    work: impl FnOnce() -> anyhow::Result<T> + Send + 'static,
+) -> anyhow::Result<T> {
+    tokio::task::spawn_blocking(work).await?
+}
+
+// `BrowserSession::capture` on a blocking thread
+pub async fn capture(
+    session: &Arc<BrowserSession>,
+    url: &str,
+    options: &RenderOptions,
+) -> anyhow::Result<Capture> {
+    let (session, url, options) = (session.clone(), url.to_owned(), options.clone());
+    blocking(move || session.capture(&url, &options)).await
+}
+
+pub fn pdf_to_text(pdf: Vec<u8>) -> anyhow::Result<String> {
     //code below uses dynamically linked libpdfium.dylib on a M1 Mac
     //it takes some efforts to bind libpdfium on different platforms
     //please visit https://github.com/ajrcarey/pdfium-render/tree/master
@@ -55,7 +63,7 @@ pub async fn get_webpage_text_headless(url: &str) -> anyhow::Result<String> {
         ))
         .or_else(|_| Pdfium::bind_to_system_library())?,
     )
-    .load_pdf_from_byte_vec(pdf_as_vec, Some(""))?
+    .load_pdf_from_byte_vec(pdf, Some(""))?
     .pages()
     .iter()
     .map(|page| page.text().unwrap().all())
@@ -65,42 +73,43 @@ pub async fn get_webpage_text_headless(url: &str) -> anyhow::Result<String> {
     Ok(text)
 }
 
-pub async fn get_html_headless(url: &str) -> anyhow::Result<String> {
-    let options = LaunchOptions {
-        headless: true,
-        window_size: Some((820, 1180)),
-        // path: Some(PathBuf::from_str("/usr/bin/google-chrome").unwrap()),
-        ..Default::default()
-    };
-
-    let browser = Browser::new(options)?;
-    let tab = browser.new_tab()?;
-    tab.navigate_to(url)?;
-    tab.wait_until_navigated()?;
-    let text = tab.get_content()?;
-
-    Ok(text)
-}
-
 pub async fn extract_article_text_from_html(url: &str, html_str: String) -> anyhow::Result<String> {
     let parsed_url = Url::parse(url)?;
     let scheme = parsed_url.scheme();
     let host = parsed_url.host_str().unwrap_or("");
     let base_url = Url::parse(&format!("{}://{}", scheme, host))?;
 
-    let res = Readability::extract(&html_str, Some(base_url)).await?;
+    let res = Readability::extract(&html_str, Some(base_url), &ExtractOptions::default()).await?;
     let output = html2text::from_read(res.to_string().as_bytes(), 80);
 
     Ok(output)
 }
 
 pub async fn text_to_use(url: &str) -> anyhow::Result<String> {
-    let pdf_text = get_webpage_text_headless(url).await?;
-    let html_str = get_html_headless(url).await?;
+    text_to_use_with_options(url, &RenderOptions::default()).await
+}
+
+pub async fn text_to_use_with_options(
+    url: &str,
+    options: &RenderOptions,
+) -> anyhow::Result<String> {
+    let session = blocking(|| BrowserSession::new(SessionOptions::default())).await?;
+    text_to_use_with_session(&Arc::new(session), url, options).await
+}
+
+// pdf and html are taken from a single navigation in a tab of `session`
+pub async fn text_to_use_with_session(
+    session: &Arc<BrowserSession>,
+    url: &str,
+    options: &RenderOptions,
+) -> anyhow::Result<String> {
+    let capture = capture(session, url, options).await?;
+    let pdf_text = pdf_to_text(capture.pdf)?;
+    let html_str = capture.html;
     let readah_text = extract_article_text_from_html(url, html_str).await?;
 
-    let readah_text_len = readah_text.split_whitespace().count();
-    let pdf_text_len = pdf_text.split_whitespace().count();
+    let readah_text_len = TextStats::from_text(&readah_text).word_count;
+    let pdf_text_len = TextStats::from_text(&pdf_text).word_count;
 
     let lots_of_text_on_page = pdf_text_len > 999;
     let readah_sees_lots_of_texts = readah_text_len > 500;
@@ -0,0 +1,222 @@
+use chrono::{DateTime, Utc};
+use libxml::xpath::Context;
+use serde_json::Value;
+
+use super::util::Util;
+
+const ARTICLE_TYPES: &[&str] = &[
+    "Article",
+    "AdvertiserContentArticle",
+    "AnalysisNewsArticle",
+    "AskPublicNewsArticle",
+    "BackgroundNewsArticle",
+    "BlogPosting",
+    "DiscussionForumPosting",
+    "LiveBlogPosting",
+    "NewsArticle",
+    "OpinionNewsArticle",
+    "Report",
+    "ReportageNewsArticle",
+    "ReviewNewsArticle",
+    "SatiricalArticle",
+    "ScholarlyArticle",
+    "SocialMediaPosting",
+    "TechArticle",
+];
+
+// Article metadata from a schema.org `<script type="application/ld+json">` block.
+// Has to be parsed before `prep_content` strips all scripts from the document.
+#[derive(Clone, Debug, Default)]
+pub struct JsonLdArticle {
+    pub article_type: Option<String>,
+    pub headline: Option<String>,
+    pub authors: Vec<String>,
+    pub date_published: Option<DateTime<Utc>>,
+    pub date_modified: Option<DateTime<Utc>>,
+    pub image: Option<String>,
+    pub publisher: Option<String>,
+    pub description: Option<String>,
+    pub article_body: Option<String>,
+    pub in_language: Option<String>,
+}
+
+impl JsonLdArticle {
+    pub fn parse(context: &Context) -> Option<Self> {
+        let scripts =
+            Util::evaluate_xpath(context, "//script[@type='application/ld+json']", false).ok()?;
+
+        let mut objects = Vec::new();
+        for script in scripts {
+            // some sites wrap the json in CDATA comments
+            let content = script.get_content();
+            let content = content
+                .trim()
+                .trim_start_matches("//<![CDATA[")
+                .trim_end_matches("//]]>");
+
+            match serde_json::from_str::<Value>(content) {
+                Ok(json) => Self::flatten(json, &mut objects),
+                Err(error) => log::debug!("Failed to parse JSON-LD: {error}"),
+            }
+        }
+
+        objects
+            .iter()
+            .find(|object| Self::is_article(object))
+            .or_else(|| {
+                objects
+                    .iter()
+                    .find(|object| object.get("headline").is_some())
+            })
+            .map(Self::from_value)
+    }
+
+    pub fn from_value(object: &Value) -> Self {
+        let string = |key: &str| {
+            object
+                .get(key)
+                .and_then(Value::as_str)
+                .map(|value| escaper::decode_html(value).unwrap_or_else(|_| value.into()))
+                .map(|value| value.trim().to_string())
+                .filter(|value| !value.is_empty())
+        };
+        let date = |key: &str| string(key).as_deref().and_then(Util::parse_date);
+
+        Self {
+            article_type: Self::types(object).into_iter().next(),
+            headline: string("headline").or_else(|| string("name")),
+            authors: match object.get("author") {
+                Some(Value::Array(authors)) => authors.iter().filter_map(Self::name).collect(),
+                Some(author) => Self::name(author).into_iter().collect(),
+                None => Vec::new(),
+            },
+            date_published: date("datePublished").or_else(|| date("dateCreated")),
+            date_modified: date("dateModified"),
+            image: object
+                .get("image")
+                .or_else(|| object.get("thumbnailUrl"))
+                .and_then(Self::image_url),
+            publisher: object.get("publisher").and_then(Self::name),
+            description: string("description"),
+            article_body: string("articleBody"),
+            in_language: string("inLanguage"),
+        }
+    }
+
+    // Collect all objects of a JSON-LD document: top level arrays, `@graph`
+    // containers and articles nested as `mainEntity` of a `WebPage`.
+    fn flatten(json: Value, objects: &mut Vec<Value>) {
+        match json {
+            Value::Array(items) => {
+                for item in items {
+                    Self::flatten(item, objects);
+                }
+            }
+            Value::Object(mut map) => {
+                if let Some(graph) = map.remove("@graph") {
+                    Self::flatten(graph, objects);
+                }
+                if let Some(main_entity) = map.get("mainEntity").cloned() {
+                    Self::flatten(main_entity, objects);
+                }
+                objects.push(Value::Object(map));
+            }
+            _ => {}
+        }
+    }
+
+    fn types(object: &Value) -> Vec<String> {
+        let types = match object.get("@type") {
+            Some(Value::String(t)) => vec![t.as_str()],
+            Some(Value::Array(types)) => types.iter().filter_map(Value::as_str).collect(),
+            _ => Vec::new(),
+        };
+
+        types
+            .into_iter()
+            .map(|t| {
+                t.trim_start_matches("http://schema.org/")
+                    .trim_start_matches("https://schema.org/")
+                    .trim_start_matches("schema:")
+                    .to_string()
+            })
+            .collect()
+    }
+
+    fn is_article(object: &Value) -> bool {
+        Self::types(object)
+            .iter()
+            .any(|t| ARTICLE_TYPES.contains(&t.as_str()))
+    }
+
+    fn name(value: &Value) -> Option<String> {
+        let name = match value {
+            Value::String(name) => name.as_str(),
+            Value::Object(object) => object.get("name")?.as_str()?,
+            _ => return None,
+        };
+        let name = name.trim();
+
+        if name.is_empty() {
+            None
+        } else {
+            Some(name.to_string())
+        }
+    }
+
+    fn image_url(image: &Value) -> Option<String> {
+        match image {
+            Value::String(url) => Some(url.clone()),
+            Value::Object(image) => image
+                .get("url")
+                .or_else(|| image.get("contentUrl"))
+                .and_then(Value::as_str)
+                .map(String::from),
+            Value::Array(images) => images.iter().find_map(Self::image_url),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use libxml::{parser::Parser, xpath::Context};
+
+    use super::JsonLdArticle;
+
+    #[test]
+    fn parse_graph() {
+        let html = r#"<html><head><script type="application/ld+json">
+        {
+            "@context": "https://schema.org",
+            "@graph": [
+                { "@type": "WebSite", "name": "The Daily" },
+                {
+                    "@type": ["NewsArticle"],
+                    "headline": "Big Story",
+                    "author": [{ "@type": "Person", "name": "Jane Doe" }, "John Roe"],
+                    "datePublished": "2023-05-04T08:00:00Z",
+                    "image": [{ "@type": "ImageObject", "url": "https://example.com/a.jpg" }],
+                    "publisher": { "@type": "Organization", "name": "Daily Co" },
+                    "articleBody": "Body text."
+                }
+            ]
+        }
+        </script></head><body></body></html>"#;
+
+        let document = Parser::default_html().parse_string(html).unwrap();
+        let context = Context::new(&document).unwrap();
+        let article = JsonLdArticle::parse(&context).unwrap();
+
+        assert_eq!(article.article_type.as_deref(), Some("NewsArticle"));
+        assert_eq!(article.headline.as_deref(), Some("Big Story"));
+        assert_eq!(article.authors, vec!["Jane Doe", "John Roe"]);
+        assert_eq!(
+            article.date_published.map(|d| d.to_rfc3339()).as_deref(),
+            Some("2023-05-04T08:00:00+00:00")
+        );
+        assert_eq!(article.image.as_deref(), Some("https://example.com/a.jpg"));
+        assert_eq!(article.publisher.as_deref(), Some("Daily Co"));
+        assert_eq!(article.article_body.as_deref(), Some("Body text."));
+    }
+}
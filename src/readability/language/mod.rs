@@ -0,0 +1,292 @@
+use std::collections::HashSet;
+
+use once_cell::sync::Lazy;
+
+// languages written right to left, by their ISO 639-1 (or 639-2) code
+const RTL_LANGUAGES: [&str; 12] = [
+    "ar", "arc", "dv", "fa", "ha", "he", "iw", "ks", "ku", "ps", "ur", "yi",
+];
+
+// letters the text needs before a guess is attempted
+const MIN_LETTERS: usize = 20;
+// only the start of long articles is looked at
+const MAX_WORDS: usize = 2000;
+
+// Common function words of languages written in latin script. Words shared
+// between languages count for all of them, the distinct ones decide.
+const STOPWORDS: [(&str, &str); 17] = [
+    ("en", "the and of to in is that it for was on are with as this be at by have from not but they which you he she we has were"),
+    ("de", "der die und in den von zu das mit sich des auf für ist im dem nicht ein eine als auch es an werden aus er hat dass sie nach wird bei"),
+    ("fr", "le la les de des et est un une du en que qui dans pour pas sur au avec ce il sont par plus se ne aux ont été cette"),
+    ("es", "el la los las de del y que en un una es por con para se no lo al como más pero sus le ha este está son fue también"),
+    ("it", "il la di che e è del della per un una in non sono con si da le dei gli al nel alla più anche come ma questo ha lo"),
+    ("pt", "o a os as de do da dos das e que em um uma para com não por no na se mais ao é foi como mas também são seu"),
+    ("nl", "de het een en van in is dat op te zijn voor met die niet aan er om ook als bij door maar naar wordt hij heeft dan nog worden"),
+    ("sv", "och att det i som en på är av för med till den har de inte om ett men var sig så från vid kan jag eller nu efter också"),
+    ("da", "og at det i som en på er af for med til den har de ikke om et men var sig så fra ved kan jeg eller nu efter også"),
+    ("nb", "og å det i som en på er av for med til den har de ikke om et men var seg så fra ved kan jeg eller nå etter også"),
+    ("pl", "i w na z się do nie że to jest o jak a od po przez dla są co ale tak czy jego już tym może być oraz który także"),
+    ("cs", "a se na v je že to s z do o pro jako by ve k jsou ale i jeho po za od už které který také bylo byl tak"),
+    ("tr", "ve bir bu da de için ile çok gibi daha olarak olan ne ama sonra kadar en her değil var o mi ben şey diye onun ya veya göre yok"),
+    ("fi", "ja on ei se että oli hän kun mutta ovat myös tai joka kuin sen ole niin jos vain sitä nyt jo mitä tämä olla jotka voi kanssa sekä"),
+    ("id", "yang dan di ini itu dengan untuk tidak dari dalam akan pada juga ke ada karena oleh mereka bisa sudah saya atau seperti lebih telah kami kita adalah tersebut hanya"),
+    ("ro", "și de în la cu a că pe nu o un care din se mai pentru este sunt ca sau fost au lui ale acest dar cum după fi când"),
+    ("hu", "a az és hogy nem is egy van meg de ki el már csak mint még ez azt volt lesz mert vagy ha kell minden után pedig között szerint amely"),
+];
+
+static STOPWORD_SETS: Lazy<Vec<(&'static str, HashSet<&'static str>)>> = Lazy::new(|| {
+    STOPWORDS
+        .iter()
+        .map(|(language, words)| (*language, words.split(' ').collect()))
+        .collect()
+});
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TextDirection {
+    Ltr,
+    Rtl,
+}
+
+impl TextDirection {
+    // Parse the value of a `dir` attribute. `auto` leaves the decision to the
+    // content and yields `None`.
+    pub fn parse(dir: &str) -> Option<Self> {
+        match dir.trim().to_lowercase().as_str() {
+            "ltr" => Some(Self::Ltr),
+            "rtl" => Some(Self::Rtl),
+            _ => None,
+        }
+    }
+
+    // Direction a language is written in, from its BCP 47 tag like "ar-EG".
+    pub fn of_language(language: &str) -> Self {
+        let primary = language
+            .split(['-', '_'])
+            .next()
+            .unwrap_or_default()
+            .to_lowercase();
+        if RTL_LANGUAGES.contains(&primary.as_str()) {
+            Self::Rtl
+        } else {
+            Self::Ltr
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Ltr => "ltr",
+            Self::Rtl => "rtl",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+enum Script {
+    Latin,
+    Arabic,
+    Hebrew,
+    Cyrillic,
+    Greek,
+    Han,
+    Kana,
+    Hangul,
+    Thai,
+    Devanagari,
+    Bengali,
+    Tamil,
+    Georgian,
+    Armenian,
+}
+
+impl Script {
+    fn of(c: char) -> Option<Self> {
+        let script = match c as u32 {
+            0x0041..=0x005A | 0x0061..=0x007A | 0x00C0..=0x024F => Self::Latin,
+            0x0370..=0x03FF => Self::Greek,
+            0x0400..=0x04FF => Self::Cyrillic,
+            0x0530..=0x058F => Self::Armenian,
+            0x0590..=0x05FF => Self::Hebrew,
+            0x0600..=0x06FF | 0x0750..=0x077F | 0xFB50..=0xFDFF | 0xFE70..=0xFEFF => Self::Arabic,
+            0x0900..=0x097F => Self::Devanagari,
+            0x0980..=0x09FF => Self::Bengali,
+            0x0B80..=0x0BFF => Self::Tamil,
+            0x0E00..=0x0E7F => Self::Thai,
+            0x10A0..=0x10FF => Self::Georgian,
+            0x1100..=0x11FF | 0x3130..=0x318F | 0xAC00..=0xD7AF => Self::Hangul,
+            0x3040..=0x30FF => Self::Kana,
+            0x3400..=0x4DBF | 0x4E00..=0x9FFF => Self::Han,
+            _ => return None,
+        };
+        Some(script)
+    }
+}
+
+pub struct LanguageDetector;
+
+impl LanguageDetector {
+    // Statistical guess of the language of `text` as ISO 639-1 code. The script
+    // decides for most languages, text in latin script is scored by its most
+    // common words. Returns `None` if the text is too short or ambiguous.
+    pub fn detect(text: &str) -> Option<&'static str> {
+        let mut counts = Vec::<(Script, usize)>::new();
+        for script in text.chars().filter_map(Script::of) {
+            match counts.iter_mut().find(|(known, _)| *known == script) {
+                Some((_, count)) => *count += 1,
+                None => counts.push((script, 1)),
+            }
+        }
+
+        let letters = counts.iter().map(|(_, count)| count).sum::<usize>();
+        if letters < MIN_LETTERS {
+            return None;
+        }
+        let count = |script| {
+            counts
+                .iter()
+                .find(|(known, _)| *known == script)
+                .map(|(_, count)| *count)
+                .unwrap_or(0)
+        };
+        let (script, _) = counts.iter().max_by_key(|(_, count)| *count)?;
+
+        match script {
+            Script::Latin => Self::detect_latin(text),
+            // Japanese mixes kanji with kana, Chinese has no kana at all
+            Script::Han | Script::Kana => {
+                if count(Script::Kana) * 10 >= count(Script::Han) {
+                    Some("ja")
+                } else {
+                    Some("zh")
+                }
+            }
+            Script::Arabic => Some(Self::detect_arabic(text)),
+            Script::Cyrillic => Some(Self::detect_cyrillic(text)),
+            Script::Hebrew => Some("he"),
+            Script::Greek => Some("el"),
+            Script::Hangul => Some("ko"),
+            Script::Thai => Some("th"),
+            Script::Devanagari => Some("hi"),
+            Script::Bengali => Some("bn"),
+            Script::Tamil => Some("ta"),
+            Script::Georgian => Some("ka"),
+            Script::Armenian => Some("hy"),
+        }
+    }
+
+    fn detect_latin(text: &str) -> Option<&'static str> {
+        let words = text
+            .split(|c: char| !c.is_alphabetic())
+            .filter(|word| !word.is_empty())
+            .take(MAX_WORDS)
+            .map(str::to_lowercase)
+            .collect::<Vec<_>>();
+
+        let (language, hits) = STOPWORD_SETS
+            .iter()
+            .map(|(language, stopwords)| {
+                let hits = words
+                    .iter()
+                    .filter(|word| stopwords.contains(word.as_str()))
+                    .count();
+                (*language, hits)
+            })
+            .rev()
+            .max_by_key(|(_, hits)| *hits)?;
+
+        // function words make up a good part of any real text
+        (hits >= 5 && hits * 10 >= words.len()).then_some(language)
+    }
+
+    fn detect_arabic(text: &str) -> &'static str {
+        let count = |letters: &[char]| text.chars().filter(|c| letters.contains(c)).count();
+
+        // letters only used by Urdu, and the Persian forms of yeh and kaf
+        // against the Arabic ones
+        let urdu = count(&['ٹ', 'ڈ', 'ڑ', 'ں', 'ے']);
+        let persian = count(&['پ', 'چ', 'ژ', 'گ', 'ی', 'ک']);
+        let arabic = count(&['ي', 'ك', 'ة']);
+
+        if urdu * 20 > persian + arabic {
+            "ur"
+        } else if persian > arabic {
+            "fa"
+        } else {
+            "ar"
+        }
+    }
+
+    fn detect_cyrillic(text: &str) -> &'static str {
+        let count = |letters: &[char]| {
+            text.chars()
+                .flat_map(char::to_lowercase)
+                .filter(|c| letters.contains(c))
+                .count()
+        };
+
+        if count(&['і', 'ї', 'є', 'ґ']) > 0 && count(&['ы', 'э']) == 0 {
+            "uk"
+        } else if count(&['ђ', 'ћ', 'џ', 'љ', 'њ', 'ј']) > 0 {
+            "sr"
+        } else if count(&['ъ']) > 0 && count(&['ы', 'э']) == 0 {
+            "bg"
+        } else {
+            "ru"
+        }
+    }
+}
+
+// Clean up a declared language like "en_US" or "de, en" to a single BCP 47
+// tag like "en-US".
+pub(crate) fn normalize_language(language: &str) -> Option<String> {
+    let language = language
+        .split([',', ';'])
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .replace('_', "-");
+
+    (!language.is_empty()
+        && language
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-'))
+    .then_some(language)
+}
+
+// Chinese and Japanese are written without spaces between words, every
+// character of these scripts reads about like a word.
+pub(crate) fn is_cjk(c: char) -> bool {
+    matches!(Script::of(c), Some(Script::Han | Script::Kana))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{LanguageDetector, TextDirection};
+
+    #[test]
+    fn detect_language() {
+        let samples = [
+            ("en", "The committee said on Tuesday that it was not able to confirm the report, which has been shared widely by people on social media."),
+            ("de", "Der Ausschuss teilte am Dienstag mit, dass er den Bericht nicht bestätigen könne, der in den sozialen Medien weit verbreitet wurde."),
+            ("fr", "Le comité a déclaré mardi qu'il n'était pas en mesure de confirmer le rapport, qui a été largement partagé sur les réseaux sociaux."),
+            ("es", "El comité dijo el martes que no podía confirmar el informe, que ha sido ampliamente compartido por la gente en las redes sociales."),
+            ("nl", "Het comité zei dinsdag dat het het rapport niet kon bevestigen, dat door mensen op sociale media op grote schaal is gedeeld."),
+            ("ru", "Комитет заявил во вторник, что не может подтвердить сообщение, которое широко распространялось в социальных сетях."),
+            ("uk", "Комітет заявив у вівторок, що не може підтвердити повідомлення, яке широко поширювалося в соціальних мережах."),
+            ("ar", "قالت اللجنة يوم الثلاثاء إنها لم تتمكن من تأكيد التقرير الذي انتشر على نطاق واسع في وسائل التواصل الاجتماعي."),
+            ("fa", "کمیته روز سه‌شنبه گفت که نمی‌تواند این گزارش را که به طور گسترده در شبکه‌های اجتماعی پخش شده است تأیید کند."),
+            ("he", "הוועדה אמרה ביום שלישי כי אינה יכולה לאשר את הדיווח, שהופץ באופן נרחב ברשתות החברתיות."),
+            ("ja", "委員会は火曜日、ソーシャルメディアで広く共有されている報告を確認できないと述べた。"),
+            ("zh", "委员会星期二表示，无法证实这份在社交媒体上广泛流传的报告，调查仍在继续进行当中。"),
+            ("ko", "위원회는 화요일 소셜 미디어에서 널리 공유된 보고서를 확인할 수 없다고 밝혔다."),
+        ];
+
+        for (language, text) in samples {
+            assert_eq!(LanguageDetector::detect(text), Some(language), "{text}");
+        }
+        assert_eq!(LanguageDetector::detect("Too short"), None);
+
+        assert_eq!(TextDirection::of_language("ar-EG"), TextDirection::Rtl);
+        assert_eq!(TextDirection::of_language("en"), TextDirection::Ltr);
+        assert_eq!(TextDirection::parse("auto"), None);
+    }
+}
@@ -0,0 +1,632 @@
+use libxml::tree::{Node, NodeType};
+
+use super::encoding::HtmlEncoding;
+use super::FullTextParserError;
+
+const BLOCK_ELEMS: &[&str] = &[
+    "address",
+    "article",
+    "aside",
+    "blockquote",
+    "body",
+    "dd",
+    "details",
+    "div",
+    "dl",
+    "dt",
+    "fieldset",
+    "figcaption",
+    "figure",
+    "footer",
+    "form",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "header",
+    "hgroup",
+    "hr",
+    "html",
+    "li",
+    "main",
+    "nav",
+    "ol",
+    "p",
+    "pre",
+    "section",
+    "summary",
+    "table",
+    "ul",
+];
+const IGNORED_ELEMS: &[&str] = &[
+    "button", "head", "input", "noscript", "script", "select", "style", "template", "textarea",
+];
+
+// How links are written: `[text](url)` or `[text][1]` with the URLs
+// collected at the end of the document.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum LinkStyle {
+    #[default]
+    Inline,
+    Reference,
+}
+
+struct Block {
+    text: String,
+    // whether the block may directly follow paragraph text without a blank
+    // line, which CommonMark only allows for lists that can interrupt one
+    interrupts: bool,
+}
+
+// Serializes the cleaned article DOM to CommonMark. Tables and strikethrough
+// use the GitHub flavored extensions since CommonMark has no syntax for them.
+pub struct Markdown {
+    link_style: LinkStyle,
+    // (url, title) of every reference link
+    references: Vec<(String, Option<String>)>,
+}
+
+impl Markdown {
+    pub fn from_node(root: &Node, link_style: LinkStyle) -> String {
+        let mut markdown = Self {
+            link_style,
+            references: Vec::new(),
+        };
+
+        let mut output = markdown.blocks_to_string(root);
+        if !markdown.references.is_empty() {
+            output.push_str("\n\n");
+            for (index, (url, title)) in markdown.references.iter().enumerate() {
+                output.push_str(&format!(
+                    "[{}]: {}{}\n",
+                    index + 1,
+                    Self::escape_url(url),
+                    Self::link_title(title.as_deref())
+                ));
+            }
+        }
+
+        let output = output.trim();
+        if output.is_empty() {
+            String::new()
+        } else {
+            format!("{output}\n")
+        }
+    }
+
+    pub fn from_html(html: &str, link_style: LinkStyle) -> Result<String, FullTextParserError> {
+        let document = HtmlEncoding::parse_utf8(html)?;
+        let root = document
+            .get_root_element()
+            .ok_or(FullTextParserError::Xml)?;
+
+        Ok(Self::from_node(&root, link_style))
+    }
+
+    fn blocks_to_string(&mut self, node: &Node) -> String {
+        Self::join_blocks(self.blocks(node))
+    }
+
+    fn join_blocks(blocks: Vec<Block>) -> String {
+        let mut output = String::new();
+        for (index, block) in blocks.iter().enumerate() {
+            if index > 0 {
+                output.push_str("\n\n");
+            }
+            output.push_str(&block.text);
+        }
+        output
+    }
+
+    // Render the children of `node` as blocks. Consecutive inline content is
+    // wrapped into a paragraph.
+    fn blocks(&mut self, node: &Node) -> Vec<Block> {
+        let mut blocks = Vec::new();
+        let mut paragraph = String::new();
+
+        for child in node.get_child_nodes() {
+            let tag = Self::tag_name(&child);
+            if tag.as_deref().is_some_and(|tag| BLOCK_ELEMS.contains(&tag)) {
+                Self::push_paragraph(&mut blocks, &mut paragraph);
+                if let Some(block) = self.block(&child) {
+                    blocks.push(block);
+                }
+            } else {
+                paragraph.push_str(&self.inline(&child));
+            }
+        }
+        Self::push_paragraph(&mut blocks, &mut paragraph);
+
+        blocks
+    }
+
+    fn push_paragraph(blocks: &mut Vec<Block>, paragraph: &mut String) {
+        let text = Self::trim_paragraph(paragraph);
+        if !text.is_empty() {
+            blocks.push(Block {
+                text: Self::escape_line_starts(&text),
+                interrupts: false,
+            });
+        }
+        paragraph.clear();
+    }
+
+    fn block(&mut self, node: &Node) -> Option<Block> {
+        let tag = Self::tag_name(node)?;
+        let text = match tag.as_str() {
+            "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
+                let level = tag[1..].parse::<usize>().unwrap_or(1);
+                let text = Self::trim_paragraph(&self.children_inline(node)).replace('\n', " ");
+                if text.is_empty() {
+                    return None;
+                }
+                format!("{} {text}", "#".repeat(level))
+            }
+            "p" | "dt" | "summary" | "figcaption" => {
+                Self::escape_line_starts(&Self::trim_paragraph(&self.children_inline(node)))
+            }
+            "pre" => Self::code_block(node),
+            "blockquote" => {
+                let text = self.blocks_to_string(node);
+                Self::prefix_lines(&text, "> ", ">")
+            }
+            "ul" | "ol" => {
+                // an ordered list starting at another number than 1 cannot
+                // interrupt a paragraph
+                let interrupts = tag == "ul" || Self::list_start(node) == 1;
+                return self
+                    .list(node, tag == "ol")
+                    .map(|text| Block { text, interrupts });
+            }
+            "dd" => Self::prefix_lines(&self.blocks_to_string(node), "    ", ""),
+            "table" => self.table(node),
+            "hr" => "---".into(),
+            _ => self.blocks_to_string(node),
+        };
+
+        if text.trim().is_empty() {
+            None
+        } else {
+            Some(Block {
+                text,
+                interrupts: false,
+            })
+        }
+    }
+
+    fn list(&mut self, node: &Node, ordered: bool) -> Option<String> {
+        let mut number = Self::list_start(node);
+        let mut items = Vec::new();
+
+        for item in node.get_child_nodes() {
+            if Self::tag_name(&item).as_deref() != Some("li") {
+                continue;
+            }
+
+            let marker = if ordered {
+                format!("{number}. ")
+            } else {
+                "- ".into()
+            };
+            number += 1;
+
+            // a nested list directly follows the text of its item when it
+            // can interrupt a paragraph
+            let mut text = String::new();
+            for (index, block) in self.blocks(&item).into_iter().enumerate() {
+                if index > 0 {
+                    text.push_str(if block.interrupts { "\n" } else { "\n\n" });
+                }
+                text.push_str(&block.text);
+            }
+
+            let indent = " ".repeat(marker.len());
+            let text = Self::prefix_lines(&text, &indent, "");
+            items.push(format!("{marker}{}", &text[indent.len().min(text.len())..]));
+        }
+
+        if items.is_empty() {
+            None
+        } else {
+            Some(items.join("\n"))
+        }
+    }
+
+    fn list_start(node: &Node) -> usize {
+        node.get_attribute("start")
+            .and_then(|start| start.trim().parse::<usize>().ok())
+            .unwrap_or(1)
+    }
+
+    fn code_block(node: &Node) -> String {
+        let code = node
+            .get_child_nodes()
+            .into_iter()
+            .find(|child| Self::tag_name(child).as_deref() == Some("code"));
+        let language = [Some(node), code.as_ref()]
+            .into_iter()
+            .flatten()
+            .filter_map(|node| node.get_attribute("class"))
+            .flat_map(|class| {
+                class
+                    .split_whitespace()
+                    .filter_map(|class| {
+                        class
+                            .strip_prefix("language-")
+                            .or_else(|| class.strip_prefix("lang-"))
+                            .map(String::from)
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .next()
+            .unwrap_or_default();
+
+        let content = node.get_content();
+        let content = content.trim_matches('\n');
+        let fence = "`".repeat(Self::longest_backtick_run(content).max(2) + 1);
+
+        format!("{fence}{language}\n{content}\n{fence}")
+    }
+
+    fn table(&mut self, node: &Node) -> String {
+        let mut rows: Vec<Vec<String>> = Vec::new();
+        self.table_rows(node, &mut rows);
+        rows.retain(|row| !row.is_empty());
+
+        let columns = rows.iter().map(Vec::len).max().unwrap_or(0);
+        if columns == 0 {
+            return String::new();
+        }
+
+        let mut lines = Vec::new();
+        for (index, row) in rows.iter().enumerate() {
+            let mut cells = row.clone();
+            cells.resize(columns, String::new());
+            lines.push(format!("| {} |", cells.join(" | ")));
+            if index == 0 {
+                lines.push(format!("|{}", " --- |".repeat(columns)));
+            }
+        }
+        lines.join("\n")
+    }
+
+    fn table_rows(&mut self, node: &Node, rows: &mut Vec<Vec<String>>) {
+        for child in node.get_child_nodes() {
+            match Self::tag_name(&child).as_deref() {
+                Some("thead") | Some("tbody") | Some("tfoot") => self.table_rows(&child, rows),
+                Some("tr") => {
+                    let cells = child
+                        .get_child_nodes()
+                        .iter()
+                        .filter(|cell| {
+                            matches!(Self::tag_name(cell).as_deref(), Some("td") | Some("th"))
+                        })
+                        .map(|cell| {
+                            Self::trim_paragraph(&self.children_inline(cell))
+                                .replace('\n', " ")
+                                .replace('|', "\\|")
+                        })
+                        .collect();
+                    rows.push(cells);
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn children_inline(&mut self, node: &Node) -> String {
+        node.get_child_nodes()
+            .iter()
+            .map(|child| self.inline(child))
+            .collect()
+    }
+
+    fn inline(&mut self, node: &Node) -> String {
+        match node.get_type() {
+            Some(NodeType::TextNode) | Some(NodeType::CDataSectionNode) => {
+                return Self::escape_text(&Self::collapse_whitespace(&node.get_content()));
+            }
+            Some(NodeType::ElementNode) => {}
+            _ => return String::new(),
+        }
+
+        let Some(tag) = Self::tag_name(node) else {
+            return String::new();
+        };
+        if IGNORED_ELEMS.contains(&tag.as_str()) {
+            return String::new();
+        }
+
+        match tag.as_str() {
+            "br" => "\\\n".into(),
+            "strong" | "b" => Self::wrap(&self.children_inline(node), "**"),
+            "em" | "i" => Self::wrap(&self.children_inline(node), "*"),
+            "del" | "s" | "strike" => Self::wrap(&self.children_inline(node), "~~"),
+            "code" | "kbd" | "samp" => {
+                let content = Self::collapse_whitespace(&node.get_content());
+                if content.trim().is_empty() {
+                    return content;
+                }
+                let fence = "`".repeat(Self::longest_backtick_run(&content) + 1);
+                let padding = if content.starts_with('`') || content.ends_with('`') {
+                    " "
+                } else {
+                    ""
+                };
+                format!("{fence}{padding}{content}{padding}{fence}")
+            }
+            "img" => self.image(node),
+            "a" => self.link(node),
+            _ => self.children_inline(node),
+        }
+    }
+
+    fn image(&mut self, node: &Node) -> String {
+        let Some(src) = node
+            .get_attribute("src")
+            .filter(|src| !src.trim().is_empty())
+        else {
+            return String::new();
+        };
+        let alt = node
+            .get_attribute("alt")
+            .map(|alt| Self::escape_text(&Self::collapse_whitespace(alt.trim())))
+            .unwrap_or_default();
+        let title = node.get_attribute("title");
+
+        format!("![{alt}]{}", self.destination(src.trim(), title.as_deref()))
+    }
+
+    fn link(&mut self, node: &Node) -> String {
+        let text = Self::trim_paragraph(&self.children_inline(node)).replace('\n', " ");
+        let href = node
+            .get_attribute("href")
+            .map(|href| href.trim().to_string())
+            .filter(|href| {
+                !href.is_empty() && !href.starts_with('#') && !href.starts_with("javascript:")
+            });
+
+        let Some(href) = href else {
+            return text;
+        };
+        let text = if text.is_empty() {
+            Self::escape_text(&href)
+        } else {
+            text
+        };
+        let title = node.get_attribute("title");
+
+        format!("[{text}]{}", self.destination(&href, title.as_deref()))
+    }
+
+    fn destination(&mut self, url: &str, title: Option<&str>) -> String {
+        match self.link_style {
+            LinkStyle::Inline => {
+                format!("({}{})", Self::escape_url(url), Self::link_title(title))
+            }
+            LinkStyle::Reference => {
+                let reference = (
+                    url.to_string(),
+                    title
+                        .map(str::trim)
+                        .filter(|title| !title.is_empty())
+                        .map(String::from),
+                );
+                let index = match self.references.iter().position(|known| *known == reference) {
+                    Some(index) => index,
+                    None => {
+                        self.references.push(reference);
+                        self.references.len() - 1
+                    }
+                };
+                format!("[{}]", index + 1)
+            }
+        }
+    }
+
+    fn link_title(title: Option<&str>) -> String {
+        title
+            .map(str::trim)
+            .filter(|title| !title.is_empty())
+            .map(|title| format!(" \"{}\"", title.replace('"', "\\\"")))
+            .unwrap_or_default()
+    }
+
+    fn wrap(text: &str, delimiter: &str) -> String {
+        // delimiters must not be separated from the content by whitespace
+        let trimmed = text.trim();
+        if trimmed.is_empty() {
+            return text.to_string();
+        }
+        let leading = &text[..text.len() - text.trim_start().len()];
+        let trailing = &text[text.trim_end().len()..];
+        format!("{leading}{delimiter}{trimmed}{delimiter}{trailing}")
+    }
+
+    fn tag_name(node: &Node) -> Option<String> {
+        if node.get_type() != Some(NodeType::ElementNode) {
+            return None;
+        }
+        Some(node.get_name().to_lowercase())
+    }
+
+    fn collapse_whitespace(text: &str) -> String {
+        let mut output = String::with_capacity(text.len());
+        let mut last_was_space = false;
+        for c in text.chars() {
+            if c.is_whitespace() && c != '\u{a0}' {
+                if !last_was_space {
+                    output.push(' ');
+                }
+                last_was_space = true;
+            } else {
+                output.push(c);
+                last_was_space = false;
+            }
+        }
+        output
+    }
+
+    fn trim_paragraph(text: &str) -> String {
+        text.lines()
+            .map(|line| line.trim_start())
+            .collect::<Vec<_>>()
+            .join("\n")
+            .trim()
+            .trim_end_matches('\\')
+            .to_string()
+    }
+
+    fn escape_text(text: &str) -> String {
+        let mut output = String::with_capacity(text.len());
+        for c in text.chars() {
+            if matches!(c, '\\' | '*' | '_' | '`' | '[' | ']' | '<') {
+                output.push('\\');
+            }
+            output.push(c);
+        }
+        output
+    }
+
+    // Block markers only have a meaning at the start of a line, so they are
+    // escaped there after the paragraph is assembled.
+    fn escape_line_starts(text: &str) -> String {
+        text.split('\n')
+            .map(|line| {
+                if line.starts_with(['#', '>', '-', '+']) {
+                    return format!("\\{line}");
+                }
+                let digits =
+                    line.len() - line.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+                if digits > 0 && line[digits..].starts_with(['.', ')']) {
+                    return format!("{}\\{}", &line[..digits], &line[digits..]);
+                }
+                line.to_string()
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    fn escape_url(url: &str) -> String {
+        if url.contains(|c: char| c.is_whitespace() || c == '(' || c == ')' || c == '<') {
+            format!("<{}>", url.replace('<', "%3C").replace('>', "%3E"))
+        } else {
+            url.to_string()
+        }
+    }
+
+    fn prefix_lines(text: &str, prefix: &str, empty_prefix: &str) -> String {
+        text.lines()
+            .map(|line| {
+                if line.is_empty() {
+                    empty_prefix.to_string()
+                } else {
+                    format!("{prefix}{line}")
+                }
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    fn longest_backtick_run(text: &str) -> usize {
+        text.split(|c| c != '`').map(str::len).max().unwrap_or(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{LinkStyle, Markdown};
+
+    #[test]
+    fn render_article() {
+        let html = r#"<article><div id="readability-page-1">
+            <h2>Intro  <em>text</em></h2>
+            <p>Some <strong>bold</strong> and a <a href="https://example.com/a" title="A">link</a>.<br>Next line with *stars*.</p>
+            <ul><li>one</li><li>two<ol start="3"><li>three</li></ol></li></ul>
+            <blockquote><p>quoted</p><p>twice</p></blockquote>
+            <pre><code class="language-rust">fn main() {
+    println!("```");
+}</code></pre>
+            <table><thead><tr><th>a</th><th>b</th></tr></thead><tbody><tr><td>1</td><td>x | y</td></tr></tbody></table>
+            <figure><img src="https://example.com/i.png" alt="An image"><figcaption>Caption</figcaption></figure>
+            <p>Again <a href="https://example.com/a">the link</a> and <code>code</code>.</p>
+        </div></article>"#;
+
+        let expected = r#"## Intro *text*
+
+Some **bold** and a [link](https://example.com/a "A").\
+Next line with \*stars\*.
+
+- one
+- two
+
+  3. three
+
+> quoted
+>
+> twice
+
+````rust
+fn main() {
+    println!("```");
+}
+````
+
+| a | b |
+| --- | --- |
+| 1 | x \| y |
+
+![An image](https://example.com/i.png)
+
+Caption
+
+Again [the link](https://example.com/a) and `code`.
+"#;
+        assert_eq!(
+            Markdown::from_html(html, LinkStyle::Inline).unwrap(),
+            expected
+        );
+
+        let reference = Markdown::from_html(html, LinkStyle::Reference).unwrap();
+        assert!(reference.contains("[link][1]"));
+        assert!(reference.contains("![An image][2]"));
+        // the same url without a title is another reference
+        assert!(reference.contains("[the link][3]"));
+        assert!(reference.ends_with(
+            "[1]: https://example.com/a \"A\"\n[2]: https://example.com/i.png\n[3]: https://example.com/a\n"
+        ));
+    }
+
+    #[test]
+    fn escape_block_markers() {
+        let html = r#"<div>
+            <p>2019. A year</p>
+            <p># not a heading</p>
+            <p>- not a list<br>+ nor this<br>&gt; no quote<br>3) no item</p>
+            <ul><li>1. item text</li><li>a - b # c</li></ul>
+            <ol><li>one<ul><li>nested</li></ul></li></ol>
+        </div>"#;
+
+        let expected = r#"2019\. A year
+
+\# not a heading
+
+\- not a list\
+\+ nor this\
+\> no quote\
+3\) no item
+
+- 1\. item text
+- a - b # c
+
+1. one
+   - nested
+"#;
+        assert_eq!(
+            Markdown::from_html(html, LinkStyle::Inline).unwrap(),
+            expected
+        );
+    }
+}
@@ -1,23 +1,58 @@
-pub mod constants;
+#[macro_use]
 pub mod macros;
-pub mod util;
+pub mod annotate;
+pub mod chunk;
+pub mod config;
+pub mod consent;
+pub mod constants;
+pub mod encoding;
+pub mod expand;
+pub mod fetcher;
 pub mod helper;
+pub mod json_ld;
+pub mod language;
+pub mod markdown;
+pub mod options;
+pub mod readerable;
+pub mod render;
+pub mod session;
+pub mod stats;
+pub mod trace;
+pub mod util;
+pub mod wait;
 
 use constants::{
     ALTER_TO_DIV_EXCEPTIONS, BASE64_DATA_URL, BYLINE, COPY_TO_SRC, COPY_TO_SRCSET, DATA_TABLE_ATTR,
-    DEFAULT_CHAR_THRESHOLD, DEFAULT_TAGS_TO_SCORE, DEPRECATED_SIZE_ATTRIBUTE_ELEMS, IS_BASE64,
-    IS_IMAGE, MINIMUM_TOPCANDIDATES, OKAY_MAYBE_ITS_A_CANDIDATE, PRESENTATIONAL_ATTRIBUTES,
-    SCORE_ATTR, SHARE_ELEMENTS, SIBLING_CONTENT, SRC_SET_URL, TITLE_CUT_END, TITLE_CUT_FRONT,
-    TITLE_SEPARATOR, UNLIELY_CANDIDATES, UNLIKELY_ROLES, VALID_EMPTY_TAGS, WORD_COUNT,
+    DEFAULT_CHAR_THRESHOLD, DEFAULT_TAGS_TO_SCORE, DEPRECATED_SIZE_ATTRIBUTE_ELEMS,
+    EXCERPT_MAX_LENGTH, EXCERPT_MIN_LENGTH, IS_BASE64, IS_IMAGE, MIN_LEAD_IMAGE_SIZE, NODE_ID_ATTR,
+    OKAY_MAYBE_ITS_A_CANDIDATE, PRESENTATIONAL_ATTRIBUTES, SCORE_ATTR, SHARE_ELEMENTS,
+    SIBLING_CONTENT, SRC_SET_URL, TITLE_CUT_END, TITLE_CUT_FRONT, TITLE_SEPARATOR, UNLIKELY_ROLES,
+    VALID_EMPTY_TAGS, WORD_COUNT,
 };
 
+use annotate::Annotator;
 use chrono::{DateTime, Utc};
+pub use chunk::{Chunk, ChunkOptions, Chunker, TokenEncoding};
+pub use config::{ConfigCollection, ConfigEntry, ConfigError, Header, Replace};
+pub use consent::{ConsentAction, ConsentHandler, ConsentRule};
+use encoding::HtmlEncoding;
+pub use expand::ExpandOptions;
+pub use fetcher::{FetchError, FetchedPage, Fetcher};
+use json_ld::JsonLdArticle;
+pub use language::{LanguageDetector, TextDirection};
 use libxml::{
-    parser::Parser,
     tree::{Document, Node, NodeType},
     xpath::Context,
 };
+pub use markdown::{LinkStyle, Markdown};
+pub use options::{ExtractOptions, ExtractOptionsBuilder};
+pub use readerable::{Readerable, ReaderableOptions};
+pub use render::{Device, RenderOptions, Viewport};
+pub use session::{BrowserSession, Capture, SessionOptions};
+pub use stats::TextStats;
+pub use trace::{Trace, TraceAction, TraceCandidate, TraceEvent, TracePass};
 use util::Util;
+pub use wait::{Wait, WaitCondition, WaitError};
 
 use std::cmp::Ordering;
 use std::collections::HashSet;
@@ -30,6 +65,8 @@ pub struct State {
     pub clean_conditionally: bool,
     pub should_remove_title_header: bool,
     pub byline: Option<String>,
+    pub direction: Option<TextDirection>,
+    pub trace: Trace,
 }
 
 impl Default for State {
@@ -40,16 +77,22 @@ impl Default for State {
             clean_conditionally: true,
             should_remove_title_header: true,
             byline: None,
+            direction: None,
+            trace: Trace::default(),
         }
     }
 }
 
-#[derive(Error, Debug)]
-pub enum ConfigError {
-    #[error("IO error")]
-    IO(#[from] std::io::Error),
-    #[error("Unknown Error")]
-    Unknown,
+impl State {
+    pub fn with_options(options: &ExtractOptions) -> Self {
+        Self {
+            strip_unlikely: options.strip_unlikely,
+            weigh_classes: options.weigh_classes,
+            clean_conditionally: options.clean_conditionally,
+            trace: Trace::new(options.trace || options.annotate),
+            ..Self::default()
+        }
+    }
 }
 
 #[derive(Error, Debug)]
@@ -62,6 +105,8 @@ pub enum FullTextParserError {
     Url(#[from] url::ParseError),
     #[error("Http request failed")]
     Http,
+    #[error("Fetching the page failed")]
+    Fetch(#[from] FetchError),
     #[error("Config Error")]
     Config,
     #[error("IO Error")]
@@ -79,10 +124,50 @@ pub struct Article {
     pub title: Option<String>,
     pub author: Option<String>,
     pub url: Url,
+    pub canonical_url: Option<Url>,
     pub date: Option<DateTime<Utc>>,
+    pub modified_date: Option<DateTime<Utc>>,
     pub thumbnail_url: Option<String>,
+    pub excerpt: Option<String>,
+    pub site_name: Option<String>,
+    pub language: Option<String>,
+    pub detected_language: Option<String>,
+    pub direction: Option<TextDirection>,
+    pub stats: Option<TextStats>,
+    pub json_ld: Option<JsonLdArticle>,
     pub document: Option<Document>,
     pub root_node: Option<Node>,
+    pub trace: Option<Trace>,
+    pub annotated_html: Option<String>,
+}
+
+// Owned result of an extraction. Unlike `Article` it holds no libxml
+// handles, so it can be sent across threads and outlive the parsed document.
+#[derive(Clone, Debug)]
+pub struct ExtractedArticle {
+    pub title: Option<String>,
+    pub byline: Option<String>,
+    pub authors: Vec<String>,
+    // the url the article was extracted from
+    pub url: Url,
+    // `<link rel="canonical">` of the page if there is one
+    pub canonical_url: Option<Url>,
+    pub date: Option<DateTime<Utc>>,
+    pub modified_date: Option<DateTime<Utc>>,
+    pub thumbnail_url: Option<String>,
+    pub excerpt: Option<String>,
+    pub site_name: Option<String>,
+    // declared by the page with `lang`, `content-language` or JSON-LD
+    pub language: Option<String>,
+    // guessed from the extracted text, see `ExtractOptions::detect_language`
+    pub detected_language: Option<String>,
+    pub direction: Option<TextDirection>,
+    pub stats: Option<TextStats>,
+    pub content: String,
+    pub trace: Option<Trace>,
+    // source document with the extraction decisions drawn onto it,
+    // see `ExtractOptions::annotate`
+    pub annotated_html: Option<String>,
 }
 
 pub struct Readability;
@@ -91,41 +176,392 @@ impl Readability {
     pub async fn extract(
         html: &str,
         base_url: Option<url::Url>,
+        options: &ExtractOptions,
     ) -> Result<String, FullTextParserError> {
-        libxml::tree::node::set_node_rc_guard(10);
-        let empty_config = ConfigEntry::default();
+        Self::extract_article(html, base_url, options)
+            .await
+            .map(|article| article.content)
+    }
+
+    pub async fn extract_from_bytes(
+        html: &[u8],
+        content_type: Option<&str>,
+        base_url: Option<url::Url>,
+        options: &ExtractOptions,
+    ) -> Result<String, FullTextParserError> {
+        Self::extract_article_from_bytes(html, content_type, base_url, options)
+            .await
+            .map(|article| article.content)
+    }
 
+    pub async fn extract_article(
+        html: &str,
+        base_url: Option<url::Url>,
+        options: &ExtractOptions,
+    ) -> Result<ExtractedArticle, FullTextParserError> {
         let url = base_url.unwrap_or_else(|| Url::parse("http://fakehost/test/base/").unwrap());
-        let document = parse_html(html, None, &empty_config)?;
+        Self::extract_article_with_config(html, url, None, &ConfigEntry::default(), options)
+    }
+
+    // Like `extract_article`, but for html that was not decoded yet. `content_type`
+    // is the value of the HTTP Content-Type header if there is one.
+    pub async fn extract_article_from_bytes(
+        html: &[u8],
+        content_type: Option<&str>,
+        base_url: Option<url::Url>,
+        options: &ExtractOptions,
+    ) -> Result<ExtractedArticle, FullTextParserError> {
+        let html = HtmlEncoding::decode(html, content_type);
+        Self::extract_article(&html, base_url, options).await
+    }
+
+    // Run the extraction and return the source document annotated with its
+    // decisions as a standalone html page, see `ExtractOptions::annotate`.
+    pub async fn extract_annotated(
+        html: &str,
+        base_url: Option<url::Url>,
+        options: &ExtractOptions,
+    ) -> Result<String, FullTextParserError> {
+        let options = ExtractOptions {
+            annotate: true,
+            ..options.clone()
+        };
+        Self::extract_article(html, base_url, &options)
+            .await?
+            .annotated_html
+            .ok_or(FullTextParserError::Readability)
+    }
+
+    // Decide whether `html` is worth extracting at all, see `Readerable::check`.
+    // Much cheaper than the extraction itself, so pages like indexes, login
+    // walls or app shells can be skipped early.
+    pub fn is_probably_readerable(
+        html: &str,
+        options: &ReaderableOptions,
+    ) -> Result<Readerable, FullTextParserError> {
+        let document = parse_html(html, None, &ConfigEntry::default())?;
+        Readerable::check(&document, options)
+    }
+
+    // Extract an article using the site config registered for the host of `url`
+    // as well as the `global` config of the collection.
+    pub async fn extract_with_configs(
+        html: &str,
+        url: Url,
+        configs: &ConfigCollection,
+        options: &ExtractOptions,
+    ) -> Result<ExtractedArticle, FullTextParserError> {
+        let empty_config = ConfigEntry::default();
+        let config = configs.get(&url);
+        let global_config = configs.global().unwrap_or(&empty_config);
+
+        Self::extract_article_with_config(html, url, config, global_config, options)
+    }
+
+    // Download `url` and extract the article. If the site config has a
+    // `single_page_link` the print view is used instead, otherwise
+    // `next_page_link` is followed for up to `options.max_pages()` pages and the
+    // bodies of all pages are concatenated.
+    pub async fn extract_url(
+        url: Url,
+        configs: &ConfigCollection,
+        fetcher: &Fetcher,
+        options: &ExtractOptions,
+    ) -> Result<ExtractedArticle, FullTextParserError> {
+        let empty_config = ConfigEntry::default();
+        let config = configs.get(&url);
+        let global_config = configs.global().unwrap_or(&empty_config);
+
+        // redirects are followed, so the page URL is the base for everything else
+        let FetchedPage { url, html } = fetcher.fetch(&url, config, global_config).await?;
+
+        let single_page_xpath = config
+            .and_then(|config| config.single_page_link.as_deref())
+            .or(global_config.single_page_link.as_deref());
+        if let Some(single_page_url) =
+            find_page_link(&html, &url, single_page_xpath, config, global_config)
+        {
+            log::debug!("Using single page view '{single_page_url}'");
+            let single_page = fetcher
+                .fetch(&single_page_url, config, global_config)
+                .await
+                .map_err(FullTextParserError::from)
+                .and_then(|page| {
+                    Self::extract_page(&page.html, page.url, config, global_config, options)
+                });
+            match single_page {
+                Ok(article) => {
+                    return article
+                        .to_extracted()
+                        .ok_or(FullTextParserError::Readability)
+                }
+                Err(error) => {
+                    log::warn!("Single page view failed, using paginated article: {error}")
+                }
+            }
+        }
+
+        let next_page_xpath = config
+            .and_then(|config| config.next_page_link.as_deref())
+            .or(global_config.next_page_link.as_deref());
+        let mut next_page_url = find_page_link(&html, &url, next_page_xpath, config, global_config);
+        let mut article = Self::extract_page(&html, url.clone(), config, global_config, options)?;
+
+        let mut visited = HashSet::from([strip_fragment(&url)]);
+        let mut page_number = 1;
+        while let Some(next_url) = next_page_url.take() {
+            if page_number >= options.max_pages {
+                log::debug!("Reached the limit of {} pages", options.max_pages);
+                break;
+            }
+            if !visited.insert(strip_fragment(&next_url)) {
+                log::debug!("Next page '{next_url}' was already visited");
+                break;
+            }
+
+            let FetchedPage {
+                url: page_url,
+                html,
+            } = match fetcher.fetch(&next_url, config, global_config).await {
+                Ok(page) => page,
+                Err(error) => {
+                    log::warn!("Failed to download next page '{next_url}': {error}");
+                    break;
+                }
+            };
+            // a redirect may lead back to a page that was already stitched
+            if page_url != next_url && !visited.insert(strip_fragment(&page_url)) {
+                log::debug!("Next page redirected to visited page '{page_url}'");
+                break;
+            }
+
+            let page =
+                match Self::extract_page(&html, page_url.clone(), config, global_config, options) {
+                    Ok(page) => page,
+                    Err(error) => {
+                        log::warn!("Failed to extract next page '{page_url}': {error}");
+                        break;
+                    }
+                };
+
+            page_number += 1;
+            append_page(&mut article, page, page_number)?;
+            next_page_url =
+                find_page_link(&html, &page_url, next_page_xpath, config, global_config);
+        }
+
+        article
+            .to_extracted()
+            .ok_or(FullTextParserError::Readability)
+    }
+
+    fn extract_article_with_config(
+        html: &str,
+        url: Url,
+        config: Option<&ConfigEntry>,
+        global_config: &ConfigEntry,
+        options: &ExtractOptions,
+    ) -> Result<ExtractedArticle, FullTextParserError> {
+        Self::extract_page(html, url, config, global_config, options)?
+            .to_extracted()
+            .ok_or(FullTextParserError::Readability)
+    }
+
+    fn extract_page(
+        html: &str,
+        url: Url,
+        config: Option<&ConfigEntry>,
+        global_config: &ConfigEntry,
+        options: &ExtractOptions,
+    ) -> Result<Article, FullTextParserError> {
+        libxml::tree::node::set_node_rc_guard(10);
+
+        let document = parse_html(html, config, global_config)?;
         let xpath_ctx = get_xpath_ctx(&document)?;
 
-        prep_content(&xpath_ctx, None, &empty_config, &url, &document, None);
-        let mut article = Article {
-            title: None,
-            author: None,
-            url,
-            date: None,
-            thumbnail_url: None,
-            document: None,
-            root_node: None,
+        // metadata has to be read before prep_content strips <link> and <script> nodes
+        let mut article = Article::new(url.clone());
+        meta_extract(&xpath_ctx, config, Some(global_config), &mut article);
+
+        // the view is taken before prep_content, so the nodes it strips are
+        // greyed out in the annotated page instead of missing
+        let view = if options.annotate {
+            Annotator::stamp(&document);
+            Some(document.dup().map_err(|()| FullTextParserError::Xml)?)
+        } else {
+            None
         };
 
+        let mut state = State::with_options(options);
+        prep_content(
+            &xpath_ctx,
+            config,
+            global_config,
+            &url,
+            &document,
+            None,
+            &mut state.trace,
+        );
+
         let mut article_document = Document::new().map_err(|()| FullTextParserError::Xml)?;
         let mut root =
             Node::new("article", None, &document).map_err(|()| FullTextParserError::Xml)?;
         article_document.set_root_element(&root);
 
-        meta_extract(&xpath_ctx, None, None, &mut article);
-        Readability::extract_body(document, &mut root, article.title.as_deref())?;
-        post_process_document(&article_document)?;
+        let extracted_with_config = Self::extract_body_xpath(
+            &xpath_ctx,
+            &document,
+            &mut root,
+            config,
+            global_config,
+            options,
+            &mut state.trace,
+        )?;
+        let body_result = if extracted_with_config {
+            Ok(true)
+        } else {
+            Readability::extract_body_with_state(
+                document,
+                &mut root,
+                article.title.as_deref(),
+                &mut state,
+                options,
+            )
+        }
+        .and_then(|_| post_process_document(&article_document));
+
+        // fall back to the JSON-LD articleBody if scoring didn't find enough text
+        let text_length = article_document
+            .get_root_element()
+            .filter(|_| body_result.is_ok())
+            .map(|root| Util::get_inner_text(&root, true).len())
+            .unwrap_or(0);
+        let article_body = article
+            .json_ld
+            .as_ref()
+            .and_then(|json_ld| json_ld.article_body.as_deref())
+            .filter(|body| text_length < options.char_threshold && body.len() > text_length)
+            .filter(|_| !extracted_with_config);
+        if let Some(article_body) = article_body {
+            log::debug!("Using JSON-LD articleBody as article content");
+            set_text_content(&article_document, article_body)?;
+        } else {
+            body_result?;
+        }
+
+        // `root` was created for the source document, which is gone by now,
+        // so traversal has to start from the article document itself
+        if let Some(mut article_root) = article_document.get_root_element() {
+            if article.thumbnail_url.is_none() {
+                article.thumbnail_url = get_lead_image(&article_root, &article.url);
+            }
+            if article.excerpt.is_none() {
+                article.excerpt = get_first_paragraph(&article_root);
+            }
+            if options.detect_language {
+                let text = Util::get_inner_text(&article_root, true);
+                article.detected_language = LanguageDetector::detect(&text).map(String::from);
+            }
+            remove_media(&mut article_root, options, &mut state.trace);
+            article.stats = Some(TextStats::from_node(&article_root));
+        }
+
+        if article.date.is_none() {
+            article.date = state.byline.as_deref().and_then(Util::parse_date);
+        }
+
+        // an explicit `dir` wins, otherwise the language decides
+        article.direction = state.direction.or(article.direction).or_else(|| {
+            article
+                .language
+                .as_deref()
+                .or(article.detected_language.as_deref())
+                .map(TextDirection::of_language)
+        });
+
+        if article.author.is_none() {
+            article.author = state
+                .byline
+                .filter(|byline| !Util::split_byline(byline).is_empty());
+        }
 
         article.document = Some(article_document);
         article.root_node = Some(root);
-        let html = article
-            .get_content()
-            .ok_or(FullTextParserError::Readability)?;
+        article.annotated_html = view.and_then(|view| Annotator::render(&view, &state.trace));
+        article.trace = Some(state.trace).filter(|_| options.trace);
+
+        Ok(article)
+    }
+
+    // Extract the body with the `body:` XPaths of the site (or global) config instead of
+    // the readability heuristics. Every XPath is tried in order and the first one that
+    // matches anything wins. Returns false if none matched.
+    pub fn extract_body_xpath(
+        context: &Context,
+        document: &Document,
+        root: &mut Node,
+        config: Option<&ConfigEntry>,
+        global_config: &ConfigEntry,
+        options: &ExtractOptions,
+        trace: &mut Trace,
+    ) -> Result<bool, FullTextParserError> {
+        let xpaths = config
+            .map(|config| config.xpath_body.iter())
+            .into_iter()
+            .flatten()
+            .chain(global_config.xpath_body.iter());
+
+        for xpath_body in xpaths {
+            let node_vec = match Util::evaluate_xpath(context, xpath_body, false) {
+                Ok(node_vec) => node_vec,
+                Err(_) => continue,
+            };
+
+            // nodes are returned in document order, skip the ones nested in another match
+            let nodes = node_vec
+                .iter()
+                .filter(|node| Util::is_probably_visible(node))
+                .filter(|node| {
+                    !Util::get_node_ancestors(node, None)
+                        .iter()
+                        .any(|ancestor| node_vec.contains(ancestor))
+                })
+                .cloned()
+                .collect::<Vec<_>>();
+
+            if nodes.is_empty() {
+                continue;
+            }
+
+            log::debug!("Extracting body with xpath '{xpath_body}'");
+
+            let mut page =
+                Node::new("div", None, document).map_err(|()| FullTextParserError::Xml)?;
+            page.set_property("id", "readability-page-1")
+                .map_err(|error| {
+                    log::error!("{error}");
+                    FullTextParserError::Xml
+                })?;
 
-        Ok(html)
+            // clean up each match on its own so the selected nodes themselves are kept
+            for mut node in nodes {
+                node.unlink();
+                post_process_page(&mut node, options, trace)?;
+                page.add_child(&mut node).map_err(|error| {
+                    log::error!("{error}");
+                    FullTextParserError::Xml
+                })?;
+            }
+
+            root.add_child(&mut page).map_err(|error| {
+                log::error!("{error}");
+                FullTextParserError::Xml
+            })?;
+
+            return Ok(true);
+        }
+
+        Ok(false)
     }
 
     pub fn extract_body(
@@ -133,14 +569,30 @@ impl Readability {
         root: &mut Node,
         title: Option<&str>,
     ) -> Result<bool, FullTextParserError> {
-        let mut state = State::default();
+        let options = ExtractOptions::default();
+        let mut state = State::with_options(&options);
+        Self::extract_body_with_state(document, root, title, &mut state, &options)
+    }
+
+    pub fn extract_body_with_state(
+        document: Document,
+        root: &mut Node,
+        title: Option<&str>,
+        state: &mut State,
+        options: &ExtractOptions,
+    ) -> Result<bool, FullTextParserError> {
         let mut document = document;
-        let mut attempts: Vec<(Node, usize, Document)> = Vec::new();
+        let mut attempts: Vec<(Node, usize, Document, Option<TextDirection>)> = Vec::new();
         let document_cache = document
             .dup()
             .map_err(|()| FullTextParserError::Readability)?;
 
         loop {
+            state.trace.begin_pass(
+                state.strip_unlikely,
+                state.weigh_classes,
+                state.clean_conditionally,
+            );
             let mut elements_to_score = Vec::new();
             let mut node: Option<Node> = document.clone().get_root_element();
 
@@ -162,11 +614,13 @@ impl Readability {
                 };
 
                 if !Util::is_probably_visible(node_ref) {
+                    state.trace.remove(node_ref, "invisible", &[]);
                     node = Util::remove_and_next(node_ref);
                     continue;
                 }
 
-                if Self::check_byline(node_ref, &match_string, &mut state) {
+                if Self::check_byline(node_ref, &match_string, state) {
+                    state.trace.remove(node_ref, "byline", &[]);
                     node = Util::remove_and_next(node_ref);
                     continue;
                 }
@@ -175,13 +629,14 @@ impl Readability {
                     && Util::header_duplicates_title(node_ref, title)
                 {
                     state.should_remove_title_header = false;
+                    state.trace.remove(node_ref, "title_header", &[]);
                     node = Util::remove_and_next(node_ref);
                     continue;
                 }
 
                 // Remove unlikely candidates
                 if state.strip_unlikely {
-                    if UNLIELY_CANDIDATES.is_match(&match_string)
+                    if options.is_unlikely(&match_string)
                         && !OKAY_MAYBE_ITS_A_CANDIDATE.is_match(&match_string)
                         && !Util::has_ancestor_tag(
                             node_ref,
@@ -198,12 +653,14 @@ impl Readability {
                         && tag_name != "BODY"
                         && tag_name != "A"
                     {
+                        state.trace.remove(node_ref, "unlikely_candidate", &[]);
                         node = Util::remove_and_next(node_ref);
                         continue;
                     }
 
                     if let Some(role) = node_ref.get_attribute("role") {
                         if UNLIKELY_ROLES.contains(&role.as_str()) {
+                            state.trace.remove(node_ref, "unlikely_role", &[]);
                             node = Util::remove_and_next(node_ref);
                             continue;
                         }
@@ -222,6 +679,7 @@ impl Readability {
                     || tag_name == "H6")
                     && Util::is_element_without_content(node_ref)
                 {
+                    state.trace.remove(node_ref, "empty_container", &[]);
                     node = Util::remove_and_next(node_ref);
                     continue;
                 }
@@ -276,11 +734,17 @@ impl Readability {
                     // element. DIVs with only a P element inside and no text content can be
                     // safely converted into plain P elements to avoid confusing the scoring
                     // algorithm with DIVs with are, in practice, paragraphs.
-                    if Util::has_single_tag_inside_element(node_ref, "P")
-                        && Util::get_link_density(node_ref) < 0.25
-                    {
+                    let link_density = Util::has_single_tag_inside_element(node_ref, "P")
+                        .then(|| Util::get_link_density(node_ref))
+                        .filter(|link_density| *link_density < 0.25);
+                    if let Some(link_density) = link_density {
                         if let Some(new_node) = node_ref.get_first_element_child() {
                             if let Some(mut parent) = node_ref.get_parent() {
+                                state.trace.convert(
+                                    node_ref,
+                                    "div_with_single_p",
+                                    &[("link_density", link_density)],
+                                );
                                 parent
                                     .replace_child_node(new_node.clone(), node_ref.clone())
                                     .map_err(|error| {
@@ -292,10 +756,11 @@ impl Readability {
                                 continue;
                             }
                         }
-                    } else if !Util::has_child_block_element(node_ref)
-                        && node_ref.set_name("P").is_ok()
-                    {
-                        elements_to_score.push(node_ref.clone());
+                    } else if !Util::has_child_block_element(node_ref) {
+                        state.trace.convert(node_ref, "div_without_blocks", &[]);
+                        if node_ref.set_name("P").is_ok() {
+                            elements_to_score.push(node_ref.clone());
+                        }
                     }
                 }
 
@@ -345,7 +810,7 @@ impl Readability {
                     }
 
                     if Self::get_content_score(&ancestor).is_none() {
-                        Self::initialize_node(&mut ancestor, &state)?;
+                        Self::initialize_node(&mut ancestor, state, options)?;
                         candidates.push(ancestor.clone());
                     }
 
@@ -379,7 +844,12 @@ impl Readability {
                 }
             });
 
-            let top_candidates = candidates.into_iter().take(5).collect::<Vec<_>>();
+            state.trace.candidates(candidates.iter());
+
+            let top_candidates = candidates
+                .into_iter()
+                .take(options.top_candidates)
+                .collect::<Vec<_>>();
 
             let mut needed_to_create_top_candidate = false;
             let mut top_candidate = top_candidates.first().cloned().unwrap_or_else(|| {
@@ -404,7 +874,7 @@ impl Readability {
 
                 root.add_child(&mut new_top_candidate).unwrap();
 
-                Self::initialize_node(&mut new_top_candidate, &state)
+                Self::initialize_node(&mut new_top_candidate, state, options)
                     .expect("init should not fail");
                 needed_to_create_top_candidate = true;
                 new_top_candidate
@@ -418,14 +888,14 @@ impl Readability {
             if let Some(top_score) = Self::get_content_score(&top_candidate) {
                 for candidate in top_candidates.iter().skip(1) {
                     let score = Self::get_content_score(candidate).unwrap_or(0.0);
-                    if score / top_score >= 0.75 {
+                    if score / top_score >= options.candidate_score_ratio {
                         alternative_candidate_ancestors
                             .push(Util::get_node_ancestors(candidate, None));
                     }
                 }
             }
 
-            if alternative_candidate_ancestors.len() >= MINIMUM_TOPCANDIDATES {
+            if alternative_candidate_ancestors.len() >= options.min_top_candidates {
                 let mut parent_of_top_candidate = top_candidate.get_parent();
 
                 while let Some(parent) = &parent_of_top_candidate {
@@ -434,14 +904,16 @@ impl Readability {
                     }
 
                     let mut lists_containing_this_ancestor = 0;
-                    let tmp =
-                        usize::min(alternative_candidate_ancestors.len(), MINIMUM_TOPCANDIDATES);
+                    let tmp = usize::min(
+                        alternative_candidate_ancestors.len(),
+                        options.min_top_candidates,
+                    );
                     for ancestors in alternative_candidate_ancestors.iter().take(tmp) {
                         lists_containing_this_ancestor +=
                             ancestors.iter().filter(|n| n == &parent).count();
                     }
 
-                    if lists_containing_this_ancestor >= MINIMUM_TOPCANDIDATES {
+                    if lists_containing_this_ancestor >= options.min_top_candidates {
                         top_candidate = parent.clone();
                         break;
                     }
@@ -451,7 +923,7 @@ impl Readability {
             }
 
             if Self::get_content_score(&top_candidate).is_none() {
-                Self::initialize_node(&mut top_candidate, &state)?;
+                Self::initialize_node(&mut top_candidate, state, options)?;
             }
 
             let mut parent_of_top_candidate = top_candidate.get_parent();
@@ -510,8 +982,9 @@ impl Readability {
             }
 
             if Self::get_content_score(&top_candidate).is_none() {
-                Self::initialize_node(&mut top_candidate, &state)?;
+                Self::initialize_node(&mut top_candidate, state, options)?;
             }
+            state.trace.top_candidate(&top_candidate);
 
             // Now that we have the top candidate, look through its siblings for content
             // that might also be related. Things like preambles, content split by ads
@@ -520,11 +993,12 @@ impl Readability {
                 Node::new("DIV", None, &document).map_err(|()| FullTextParserError::Readability)?;
 
             let sibling_score_threshold = f64::max(
-                8.0, //lowered from 10.0
+                options.sibling_score_threshold, //lowered from 10.0
                 Self::get_content_score(&top_candidate).unwrap_or(0.0) * 0.2,
             );
             // Keep potential top candidate's parent node to try to get text direction of it later.
             parent_of_top_candidate = top_candidate.get_parent();
+            let direction = Self::get_text_direction(&top_candidate);
             let siblings = parent_of_top_candidate
                 .as_ref()
                 .map(|n| n.get_child_elements());
@@ -555,6 +1029,15 @@ impl Readability {
                         }
 
                         if score + content_bonus >= sibling_score_threshold {
+                            state.trace.keep(
+                                &sibling,
+                                "sibling_score",
+                                &[
+                                    ("content_score", score),
+                                    ("content_bonus", content_bonus),
+                                    ("threshold", sibling_score_threshold),
+                                ],
+                            );
                             append = true;
                         } else if sibling.get_name().to_uppercase() == "P" {
                             let link_density = Util::get_link_density(&sibling);
@@ -567,6 +1050,14 @@ impl Readability {
                                         && link_density == 0.0
                                         && SIBLING_CONTENT.is_match(&node_content)))
                             {
+                                state.trace.keep(
+                                    &sibling,
+                                    "sibling_paragraph",
+                                    &[
+                                        ("link_density", link_density),
+                                        ("content_length", node_length as f64),
+                                    ],
+                                );
                                 append = true;
                             }
                         }
@@ -590,6 +1081,7 @@ impl Readability {
                                 sibling.get_attribute("class")
                             );
 
+                            state.trace.convert(&sibling, "sibling_to_div", &[]);
                             sibling.set_name("DIV").map_err(|error| {
                                 log::error!("{error}");
                                 FullTextParserError::Readability
@@ -606,7 +1098,7 @@ impl Readability {
             }
 
             if state.clean_conditionally {
-                post_process_page(&mut article_content)?;
+                post_process_page(&mut article_content, options, &mut state.trace)?;
             }
 
             if needed_to_create_top_candidate {
@@ -652,26 +1144,29 @@ impl Readability {
             let text = Util::get_inner_text(&article_content, true);
             let text_length = text.len();
 
-            if text_length < DEFAULT_CHAR_THRESHOLD {
+            if text_length < options.char_threshold {
                 parse_successful = false;
+                state.trace.end_pass(text_length, false);
 
                 if state.strip_unlikely {
                     state.strip_unlikely = false;
-                    attempts.push((article_content, text_length, document));
+                    attempts.push((article_content, text_length, document, direction));
                 } else if state.weigh_classes {
                     state.weigh_classes = false;
-                    attempts.push((article_content, text_length, document));
+                    attempts.push((article_content, text_length, document, direction));
                 } else if state.clean_conditionally {
                     state.clean_conditionally = false;
-                    attempts.push((article_content, text_length, document));
+                    attempts.push((article_content, text_length, document, direction));
                 } else {
-                    attempts.push((article_content, text_length, document));
+                    attempts.push((article_content, text_length, document, direction));
                     // No luck after removing flags, just return the longest text we found during the different loops
 
-                    attempts.sort_by(|(_, size_a, _), (_, size_b, _)| size_a.cmp(size_b));
+                    attempts.sort_by_key(|(_, size, _, _)| *size);
+                    state.trace.select_longest_pass();
 
                     // But first check if we actually have something
-                    if let Some((best_attempt, _len, _document)) = attempts.pop() {
+                    if let Some((best_attempt, _len, _document, direction)) = attempts.pop() {
+                        state.direction = direction;
                         for mut child in best_attempt.get_child_nodes() {
                             child.unlink();
                             root.add_child(&mut child).map_err(|error| {
@@ -689,6 +1184,8 @@ impl Readability {
                     .dup()
                     .map_err(|()| FullTextParserError::Readability)?;
             } else {
+                state.trace.end_pass(text_length, true);
+                state.direction = direction;
                 for mut child in article_content.get_child_nodes() {
                     child.unlink();
                     root.add_child(&mut child).map_err(|error| {
@@ -701,6 +1198,22 @@ impl Readability {
         }
     }
 
+    // The `dir` of the top candidate, its parent or the closest ancestor that has one.
+    fn get_text_direction(top_candidate: &Node) -> Option<TextDirection> {
+        let parent = top_candidate.get_parent();
+        parent
+            .iter()
+            .cloned()
+            .chain(Some(top_candidate.clone()))
+            .chain(
+                parent
+                    .iter()
+                    .flat_map(|parent| Util::get_node_ancestors(parent, None)),
+            )
+            .filter_map(|node| node.get_attribute("dir"))
+            .find_map(|dir| TextDirection::parse(&dir))
+    }
+
     fn get_content_score(node: &Node) -> Option<f64> {
         node.get_attribute(SCORE_ATTR)
             .and_then(|a| a.parse::<f64>().ok())
@@ -746,7 +1259,11 @@ impl Readability {
 
     // Initialize a node with the readability object. Also checks the
     // className/id for special names to add to its score.
-    fn initialize_node(node: &mut Node, state: &State) -> Result<(), FullTextParserError> {
+    fn initialize_node(
+        node: &mut Node,
+        state: &State,
+        options: &ExtractOptions,
+    ) -> Result<(), FullTextParserError> {
         let score = match node.get_name().to_uppercase().as_str() {
             "DIV" => 5,
             "PRE" | "TD" | "BLOCKQUITE" => 3,
@@ -756,7 +1273,7 @@ impl Readability {
             _ => 0,
         };
         let class_weight = if state.weigh_classes {
-            Util::get_class_weight(node)
+            Util::get_class_weight(node, options)
         } else {
             0
         };
@@ -771,82 +1288,83 @@ impl Readability {
     }
 }
 
-#[derive(Clone, Debug)]
-pub struct Replace {
-    pub to_replace: String,
-    pub replace_with: String,
-}
+pub fn post_process_document(document: &Document) -> Result<(), FullTextParserError> {
+    if let Some(mut root) = document.get_root_element() {
+        simplify_nested_elements(&mut root)?;
+        clean_attributes(&mut root)?;
+        remove_single_cell_tables(&mut root);
+        remove_extra_p_and_div(&mut root);
+    }
 
-#[derive(Clone, Debug)]
-pub struct Header {
-    pub name: String,
-    pub value: String,
+    Ok(())
 }
 
-#[derive(Clone, Debug, Default)]
-pub struct ConfigEntry {
-    pub xpath_title: Vec<String>,
-    pub xpath_author: Vec<String>,
-    pub xpath_date: Vec<String>,
-    pub xpath_body: Vec<String>,
-    pub xpath_strip: Vec<String>,
-    pub strip_id_or_class: Vec<String>,
-    pub strip_image_src: Vec<String>,
-    pub replace: Vec<Replace>,
-    pub header: Vec<Header>,
-    pub single_page_link: Option<String>,
-    pub next_page_link: Option<String>,
-}
-macro_rules! extract_vec_multi {
-    (
-            $line: ident,
-            $identifier: ident,
-            $vector: ident
-        ) => {
-        if $line.starts_with($identifier) {
-            let value = Util::str_extract_value($identifier, $line);
-            let value = Util::split_values(value);
-            let value: Vec<String> = value.iter().map(|s| s.trim().to_string()).collect();
-            $vector.extend(value);
-            continue;
-        }
-    };
-}
+// Drop the kinds of media the caller opted out of. Figures that are left
+// without any text once their images are gone are removed as well.
+pub fn remove_media(root: &mut Node, options: &ExtractOptions, trace: &mut Trace) {
+    let mut tags = Vec::new();
+    if !options.keep_images {
+        tags.extend(["img", "picture"]);
+    }
+    if !options.keep_tables {
+        tags.push("table");
+    }
+    if !options.keep_videos {
+        tags.extend(["video", "iframe", "embed", "object"]);
+    }
 
-macro_rules! extract_vec_single {
-    (
-            $line: ident,
-            $identifier: ident,
-            $vector: ident
-        ) => {
-        if $line.starts_with($identifier) {
-            let value = Util::str_extract_value($identifier, $line);
-            $vector.push(value.to_string());
-            continue;
+    for tag in tags {
+        for mut node in Util::get_elements_by_tag_name(root, tag).into_iter().rev() {
+            trace.remove(&node, "excluded_media", &[]);
+            node.unlink();
         }
-    };
-}
+    }
 
-macro_rules! extract_option_single {
-    (
-            $line: ident,
-            $identifier: ident,
-            $option: ident
-        ) => {
-        if $line.starts_with($identifier) {
-            let value = Util::str_extract_value($identifier, $line);
-            $option = Some(value.to_string());
-            continue;
+    if !options.keep_images {
+        for mut figure in Util::get_elements_by_tag_name(root, "figure")
+            .into_iter()
+            .rev()
+        {
+            if Util::get_inner_text(&figure, true).is_empty() {
+                trace.remove(&figure, "empty_figure", &[]);
+                figure.unlink();
+            }
         }
-    };
+    }
 }
 
-pub fn post_process_document(document: &Document) -> Result<(), FullTextParserError> {
-    if let Some(mut root) = document.get_root_element() {
-        simplify_nested_elements(&mut root)?;
-        clean_attributes(&mut root)?;
-        remove_single_cell_tables(&mut root);
-        remove_extra_p_and_div(&mut root);
+// Replace the content of `document` with plain text split into paragraphs.
+pub fn set_text_content(document: &Document, text: &str) -> Result<(), FullTextParserError> {
+    let mut root = document
+        .get_root_element()
+        .ok_or(FullTextParserError::Xml)?;
+    for mut child in root.get_child_nodes() {
+        child.unlink();
+    }
+
+    let mut div = Node::new("div", None, document).map_err(|()| FullTextParserError::Xml)?;
+    div.set_property("id", "readability-page-1")
+        .map_err(|error| {
+            log::error!("{error}");
+            FullTextParserError::Xml
+        })?;
+    root.add_child(&mut div).map_err(|error| {
+        log::error!("{error}");
+        FullTextParserError::Xml
+    })?;
+
+    for paragraph in text.lines().map(str::trim).filter(|line| !line.is_empty()) {
+        let mut p = Node::new("p", None, document).map_err(|()| FullTextParserError::Xml)?;
+        let mut text =
+            Node::new_text(paragraph, document).map_err(|()| FullTextParserError::Xml)?;
+        p.add_child(&mut text).map_err(|error| {
+            log::error!("{error}");
+            FullTextParserError::Xml
+        })?;
+        div.add_child(&mut p).map_err(|error| {
+            log::error!("{error}");
+            FullTextParserError::Xml
+        })?;
     }
 
     Ok(())
@@ -858,8 +1376,13 @@ pub fn meta_extract(
     global_config: Option<&ConfigEntry>,
     article: &mut Article,
 ) {
+    if article.json_ld.is_none() {
+        article.json_ld = JsonLdArticle::parse(context);
+    }
+    let json_ld = article.json_ld.as_ref();
+
     if article.title.is_none() {
-        article.title = extract_title(context, config, global_config)
+        article.title = extract_title_with_json_ld(context, config, global_config, json_ld)
             .map(|title| match escaper::decode_html(&title) {
                 Ok(escaped_title) => escaped_title,
                 Err(_error) => title,
@@ -879,6 +1402,113 @@ pub fn meta_extract(
                 }
             });
     }
+
+    if article.author.is_none() {
+        article.author = extract_author(context, config, global_config, json_ld);
+    }
+
+    if article.date.is_none() {
+        article.date = extract_date(context, config, global_config, json_ld);
+    }
+
+    if article.modified_date.is_none() {
+        article.modified_date = extract_modified_date(context, json_ld);
+    }
+
+    if article.thumbnail_url.is_none() {
+        article.thumbnail_url = extract_thumbnail(context, &article.url, json_ld);
+    }
+
+    if article.excerpt.is_none() {
+        article.excerpt = extract_excerpt(context, json_ld);
+    }
+
+    if article.site_name.is_none() {
+        article.site_name = get_meta_content(context, "og:site_name")
+            .or_else(|| json_ld.and_then(|json_ld| json_ld.publisher.clone()));
+    }
+
+    if article.language.is_none() {
+        article.language = extract_language(context, json_ld);
+    }
+
+    if article.direction.is_none() {
+        article.direction = ["//html[@dir]", "//body[@dir]"]
+            .into_iter()
+            .filter_map(|xpath| Util::get_attribute(context, xpath, "dir").ok())
+            .find_map(|dir| TextDirection::parse(&dir));
+    }
+
+    if article.canonical_url.is_none() {
+        article.canonical_url = Util::get_attribute(context, "//link[@rel='canonical']", "href")
+            .ok()
+            .or_else(|| get_meta_content(context, "og:url"))
+            .and_then(|canonical| article.url.join(canonical.trim()).ok());
+    }
+}
+
+// Resolve a `single_page_link` or `next_page_link` XPath against the raw html
+// of a page. This has to happen before prep_content, which strips <link> nodes.
+fn find_page_link(
+    html: &str,
+    url: &Url,
+    xpath: Option<&str>,
+    config: Option<&ConfigEntry>,
+    global_config: &ConfigEntry,
+) -> Option<Url> {
+    let xpath = xpath?;
+    let document = parse_html(html, config, global_config).ok()?;
+    let context = get_xpath_ctx(&document).ok()?;
+    Util::find_page_url(&context, xpath, url)
+}
+
+fn strip_fragment(url: &Url) -> Url {
+    let mut url = url.clone();
+    url.set_fragment(None);
+    url
+}
+
+// Move the body of a following page into the article document as
+// `readability-page-N`. The nodes are owned by the document of `page`,
+// so they have to be imported rather than just re-parented.
+fn append_page(
+    article: &mut Article,
+    page: Article,
+    page_number: usize,
+) -> Result<(), FullTextParserError> {
+    let article_document = article.document.as_mut().ok_or(FullTextParserError::Xml)?;
+    let mut article_root = article_document
+        .get_root_element()
+        .ok_or(FullTextParserError::Xml)?;
+    let mut page_root = page
+        .document
+        .as_ref()
+        .and_then(|document| document.get_root_element())
+        .ok_or(FullTextParserError::Xml)?;
+
+    page_root.unlink();
+    let page_root = article_document
+        .import_node(&mut page_root)
+        .map_err(|()| FullTextParserError::Xml)?;
+
+    for mut child in page_root.get_child_nodes() {
+        child.unlink();
+        if child.get_property("id").as_deref() == Some("readability-page-1") {
+            _ = child.set_property("id", &format!("readability-page-{page_number}"));
+        }
+        article_root.add_child(&mut child).map_err(|error| {
+            log::error!("Failed to append page {page_number}: {error}");
+            FullTextParserError::Xml
+        })?;
+    }
+
+    article.stats = Some(TextStats::from_node(&article_root));
+
+    if let (Some(trace), Some(page_trace)) = (article.trace.as_mut(), page.trace) {
+        trace.append(page_trace);
+    }
+
+    Ok(())
 }
 
 pub fn parse_html(
@@ -900,11 +1530,9 @@ pub fn parse_html(
     }
 
     // parse html
-    let parser = Parser::default_html();
-    parser.parse_string(html.as_str()).map_err(|err| {
-        log::error!("Parsing HTML failed for downloaded HTML {:?}", err);
-        FullTextParserError::Xml
-    })
+    // the html is utf-8 at this point, a <meta charset> of the original
+    // encoding must not make libxml decode it a second time
+    HtmlEncoding::parse_utf8(&html)
 }
 
 pub fn get_xpath_ctx(doc: &Document) -> Result<Context, FullTextParserError> {
@@ -921,6 +1549,7 @@ pub fn prep_content(
     url: &Url,
     document: &Document,
     title: Option<&str>,
+    trace: &mut Trace,
 ) {
     // replace H1 with H2 as H1 should be only title that is displayed separately
     if let Ok(h1_nodes) = Util::evaluate_xpath(context, "//h1", false) {
@@ -932,6 +1561,7 @@ pub fn prep_content(
     if let Ok(h2_nodes) = Util::evaluate_xpath(context, "//h2", false) {
         for mut h2_node in h2_nodes {
             if Util::header_duplicates_title(&h2_node, title) {
+                trace.remove(&h2_node, "title_header", &[]);
                 h2_node.unlink();
             }
         }
@@ -949,23 +1579,23 @@ pub fn prep_content(
     // strip specified xpath
     if let Some(config) = config {
         for xpath_strip in &config.xpath_strip {
-            _ = Util::strip_node(context, xpath_strip);
+            _ = Util::strip_node(context, xpath_strip, "strip", trace);
         }
     }
 
     for xpath_strip in &global_config.xpath_strip {
-        _ = Util::strip_node(context, xpath_strip);
+        _ = Util::strip_node(context, xpath_strip, "strip", trace);
     }
 
     // strip everything with specified 'id' or 'class'
     if let Some(config) = config {
         for xpaht_strip_class in &config.strip_id_or_class {
-            _ = Util::strip_id_or_class(context, xpaht_strip_class);
+            _ = Util::strip_id_or_class(context, xpaht_strip_class, trace);
         }
     }
 
     for xpaht_strip_class in &global_config.strip_id_or_class {
-        _ = Util::strip_id_or_class(context, xpaht_strip_class);
+        _ = Util::strip_id_or_class(context, xpaht_strip_class, trace);
     }
 
     // strip any <img> element where @src attribute contains this substring
@@ -974,6 +1604,8 @@ pub fn prep_content(
             _ = Util::strip_node(
                 context,
                 &format!("//img[contains(@src,'{}')]", xpath_strip_img_src),
+                "strip_image_src",
+                trace,
             );
         }
     }
@@ -982,10 +1614,12 @@ pub fn prep_content(
         _ = Util::strip_node(
             context,
             &format!("//img[contains(@src,'{}')]", xpath_strip_img_src),
+            "strip_image_src",
+            trace,
         );
     }
 
-    _ = Util::strip_node(context, "//noscript");
+    _ = Util::strip_node(context, "//noscript", "noscript", trace);
 
     _ = fix_lazy_images(context, document);
     _ = fix_iframe_size(context, "youtube.com");
@@ -997,42 +1631,54 @@ pub fn prep_content(
     _ = Util::strip_node(
         context,
         "//*[contains(@class,' entry-unrelated ') or contains(@class,' instapaper_ignore ')]",
+        "ignore_class",
+        trace,
     );
 
     // strip elements that contain style="display: none;"
-    _ = Util::strip_node(context, "//*[contains(@style,'display:none')]");
-    _ = Util::strip_node(context, "//*[contains(@style,'display: none')]");
+    _ = Util::strip_node(
+        context,
+        "//*[contains(@style,'display:none')]",
+        "display_none",
+        trace,
+    );
+    _ = Util::strip_node(
+        context,
+        "//*[contains(@style,'display: none')]",
+        "display_none",
+        trace,
+    );
     _ = remove_attribute(context, None, "style");
 
     // strip all input elements
-    _ = Util::strip_node(context, "//form");
-    _ = Util::strip_node(context, "//input");
-    _ = Util::strip_node(context, "//textarea");
-    _ = Util::strip_node(context, "//select");
-    _ = Util::strip_node(context, "//button");
+    _ = Util::strip_node(context, "//form", "form", trace);
+    _ = Util::strip_node(context, "//input", "form", trace);
+    _ = Util::strip_node(context, "//textarea", "form", trace);
+    _ = Util::strip_node(context, "//select", "form", trace);
+    _ = Util::strip_node(context, "//button", "form", trace);
 
     // strip all comments
-    _ = Util::strip_node(context, "//comment()");
+    _ = Util::strip_node(context, "//comment()", "comment", trace);
 
     // strip all scripts
-    _ = Util::strip_node(context, "//script");
+    _ = Util::strip_node(context, "//script", "script", trace);
 
     // strip all styles
-    _ = Util::strip_node(context, "//style");
+    _ = Util::strip_node(context, "//style", "style", trace);
 
     // strip all empty url-tags <a/>
-    _ = Util::strip_node(context, "//a[not(node())]");
+    _ = Util::strip_node(context, "//a[not(node())]", "empty_link", trace);
 
     // strip all external css and fonts
-    _ = Util::strip_node(context, "//*[@type='text/css']");
+    _ = Util::strip_node(context, "//*[@type='text/css']", "stylesheet", trace);
 
     // other junk
-    _ = Util::strip_node(context, "//iframe");
-    _ = Util::strip_node(context, "//object");
-    _ = Util::strip_node(context, "//embed");
-    _ = Util::strip_node(context, "//footer");
-    _ = Util::strip_node(context, "//link");
-    _ = Util::strip_node(context, "//aside");
+    _ = Util::strip_node(context, "//iframe", "iframe", trace);
+    _ = Util::strip_node(context, "//object", "object", trace);
+    _ = Util::strip_node(context, "//embed", "embed", trace);
+    _ = Util::strip_node(context, "//footer", "footer", trace);
+    _ = Util::strip_node(context, "//link", "link", trace);
+    _ = Util::strip_node(context, "//aside", "aside", trace);
 
     if let Some(root) = document.get_root_element() {
         Util::replace_brs(&root, document);
@@ -1155,7 +1801,7 @@ pub fn remove_attribute(
     let xpath_tag = tag.unwrap_or("*");
 
     let xpath = &format!("//{}[@{}]", xpath_tag, attribute);
-    let node_vec = Util::evaluate_xpath(context, xpath, false)
+    Util::evaluate_xpath(context, xpath, false)
         .map_err(|_err| anyhow::anyhow!("Failed to evaluate XPath"))?;
 
     Ok(())
@@ -1369,6 +2015,11 @@ pub fn clean_attributes(root: &mut Node) -> Result<(), FullTextParserError> {
             FullTextParserError::Xml
         })?;
 
+        node.remove_attribute(NODE_ID_ATTR).map_err(|e| {
+            log::error!("{e}");
+            FullTextParserError::Xml
+        })?;
+
         node_iter = Util::next_node(&node, false);
     }
     Ok(())
@@ -1438,6 +2089,16 @@ pub fn extract_title(
     context: &Context,
     config: Option<&ConfigEntry>,
     global_config: Option<&ConfigEntry>,
+) -> Option<String> {
+    extract_title_with_json_ld(context, config, global_config, None)
+}
+
+// Like `extract_title`, with the JSON-LD `headline` as the last fallback.
+pub fn extract_title_with_json_ld(
+    context: &Context,
+    config: Option<&ConfigEntry>,
+    global_config: Option<&ConfigEntry>,
+    json_ld: Option<&JsonLdArticle>,
 ) -> Option<String> {
     // check site specific config
     if let Some(config) = config {
@@ -1466,6 +2127,262 @@ pub fn extract_title(
         .or_else(|| get_meta(context, "weibo:article:title"))
         .or_else(|| get_meta(context, "weibo:webpage:title"))
         .or_else(|| get_meta(context, "twitter:title"))
+        .or_else(|| json_ld.and_then(|json_ld| json_ld.headline.clone()))
+}
+
+pub fn extract_author(
+    context: &Context,
+    config: Option<&ConfigEntry>,
+    global_config: Option<&ConfigEntry>,
+    json_ld: Option<&JsonLdArticle>,
+) -> Option<String> {
+    // check site specific config
+    if let Some(config) = config {
+        for xpath_author in &config.xpath_author {
+            if let Some(author) = extract_author_nodes(context, xpath_author) {
+                return Some(author);
+            }
+        }
+    }
+
+    // check global config
+    if let Some(global_config) = global_config {
+        for xpath_author in &global_config.xpath_author {
+            if let Some(author) = extract_author_nodes(context, xpath_author) {
+                return Some(author);
+            }
+        }
+    }
+
+    json_ld
+        .filter(|json_ld| !json_ld.authors.is_empty())
+        .map(|json_ld| json_ld.authors.join(", "))
+        .or_else(|| get_meta_content(context, "author"))
+        .or_else(|| get_meta_content(context, "dc:creator"))
+        .or_else(|| get_meta_content(context, "dcterm:creator"))
+        .or_else(|| get_meta_content(context, "article:author"))
+        .or_else(|| {
+            extract_author_nodes(
+                context,
+                "//*[@rel='author' or contains(concat(' ', @itemprop, ' '), ' author ')]",
+            )
+        })
+}
+
+// Collects the text of every node matching `xpath` into a single comma separated byline.
+// Schema.org author nodes usually wrap the name in a nested `itemprop="name"` element
+// next to avatars and bios, so that is preferred over the full text content.
+fn extract_author_nodes(context: &Context, xpath: &str) -> Option<String> {
+    let nodes = Util::evaluate_xpath(context, xpath, true).ok()?;
+    let mut authors: Vec<String> = Vec::new();
+
+    for node in nodes {
+        let name_node = Util::get_elements_by_tag_name(&node, "*")
+            .into_iter()
+            .find(|n| n.get_attribute("itemprop").as_deref() == Some("name"));
+        let name = match name_node {
+            Some(name_node) => Util::get_inner_text(&name_node, true),
+            None => Util::get_inner_text(&node, true),
+        };
+        if !name.is_empty() && name.len() < 100 && !authors.contains(&name) {
+            authors.push(name);
+        }
+    }
+
+    if authors.is_empty() {
+        None
+    } else {
+        Some(authors.join(", "))
+    }
+}
+
+pub fn extract_date(
+    context: &Context,
+    config: Option<&ConfigEntry>,
+    global_config: Option<&ConfigEntry>,
+    json_ld: Option<&JsonLdArticle>,
+) -> Option<DateTime<Utc>> {
+    // check site specific config
+    if let Some(config) = config {
+        for xpath_date in &config.xpath_date {
+            if let Some(date) = Util::extract_value(context, xpath_date)
+                .ok()
+                .and_then(|date| Util::parse_date(&date))
+            {
+                return Some(date);
+            }
+        }
+    }
+
+    // check global config
+    if let Some(global_config) = global_config {
+        for xpath_date in &global_config.xpath_date {
+            if let Some(date) = Util::extract_value(context, xpath_date)
+                .ok()
+                .and_then(|date| Util::parse_date(&date))
+            {
+                return Some(date);
+            }
+        }
+    }
+
+    if let Some(date) = json_ld.and_then(|json_ld| json_ld.date_published) {
+        return Some(date);
+    }
+
+    [
+        get_meta_content(context, "article:published_time"),
+        get_meta_content(context, "og:published_time"),
+        get_meta_content(context, "datePublished"),
+        get_meta_content(context, "pubdate"),
+        get_meta_content(context, "publish-date"),
+        get_meta_content(context, "parsely-pub-date"),
+        get_meta_content(context, "sailthru.date"),
+        get_meta_content(context, "dc.date"),
+        get_meta_content(context, "DC.date.issued"),
+        get_meta_content(context, "date"),
+        Util::get_attribute(context, "//time[@pubdate][@datetime]", "datetime").ok(),
+        Util::get_attribute(context, "//time[@itemprop='datePublished']", "datetime").ok(),
+        Util::get_attribute(context, "//time[@datetime]", "datetime").ok(),
+    ]
+    .into_iter()
+    .flatten()
+    .find_map(|date| Util::parse_date(&date))
+}
+
+pub fn extract_modified_date(
+    context: &Context,
+    json_ld: Option<&JsonLdArticle>,
+) -> Option<DateTime<Utc>> {
+    if let Some(date) = json_ld.and_then(|json_ld| json_ld.date_modified) {
+        return Some(date);
+    }
+
+    [
+        get_meta_content(context, "article:modified_time"),
+        get_meta_content(context, "og:updated_time"),
+        get_meta_content(context, "dateModified"),
+        get_meta_content(context, "last-modified"),
+        Util::get_attribute(context, "//time[@itemprop='dateModified']", "datetime").ok(),
+    ]
+    .into_iter()
+    .flatten()
+    .find_map(|date| Util::parse_date(&date))
+}
+
+pub fn extract_language(context: &Context, json_ld: Option<&JsonLdArticle>) -> Option<String> {
+    [
+        Util::get_attribute(context, "//html[@lang]", "lang").ok(),
+        Util::get_attribute(
+            context,
+            "//meta[translate(@http-equiv, 'CONTENT-LANGUAGE', 'content-language')='content-language']",
+            "content",
+        )
+        .ok(),
+        get_meta_content(context, "og:locale"),
+        json_ld.and_then(|json_ld| json_ld.in_language.clone()),
+    ]
+    .into_iter()
+    .flatten()
+    .find_map(|language| language::normalize_language(&language))
+}
+
+pub fn extract_excerpt(context: &Context, json_ld: Option<&JsonLdArticle>) -> Option<String> {
+    [
+        get_meta_content(context, "og:description"),
+        get_meta_content(context, "description"),
+        get_meta_content(context, "twitter:description"),
+        json_ld.and_then(|json_ld| json_ld.description.clone()),
+    ]
+    .into_iter()
+    .flatten()
+    .map(|excerpt| escaper::decode_html(&excerpt).unwrap_or(excerpt))
+    .map(|excerpt| excerpt.split_whitespace().collect::<Vec<_>>().join(" "))
+    .find(|excerpt| !excerpt.is_empty())
+}
+
+// Fallback excerpt for pages without a description: the first paragraph of the
+// extracted content that is long enough to say something about the article.
+pub fn get_first_paragraph(root: &Node) -> Option<String> {
+    let paragraph = Util::get_elements_by_tag_name(root, "p")
+        .iter()
+        .map(|p| Util::get_inner_text(p, true))
+        .find(|text| text.chars().count() >= EXCERPT_MIN_LENGTH)?;
+
+    if paragraph.chars().count() <= EXCERPT_MAX_LENGTH {
+        return Some(paragraph);
+    }
+
+    // cut at the last word boundary that fits
+    let end = paragraph
+        .char_indices()
+        .nth(EXCERPT_MAX_LENGTH)
+        .map(|(index, _)| index)
+        .unwrap_or(paragraph.len());
+    let cut = paragraph[..end].rfind(char::is_whitespace).unwrap_or(end);
+    Some(format!("{}…", paragraph[..cut].trim_end()))
+}
+
+pub fn extract_thumbnail(
+    context: &Context,
+    url: &Url,
+    json_ld: Option<&JsonLdArticle>,
+) -> Option<String> {
+    get_meta_content(context, "og:image:secure_url")
+        .or_else(|| get_meta_content(context, "og:image"))
+        .or_else(|| get_meta_content(context, "twitter:image"))
+        .or_else(|| get_meta_content(context, "twitter:image:src"))
+        .or_else(|| json_ld.and_then(|json_ld| json_ld.image.clone()))
+        .or_else(|| Util::get_attribute(context, "//link[@rel='image_src']", "href").ok())
+        .and_then(|thumbnail| url.join(thumbnail.trim()).ok())
+        .map(|thumbnail| thumbnail.to_string())
+}
+
+// Pick the first image of the extracted content that is big enough to serve as a
+// lead image. The largest srcset candidate is preferred over the plain src.
+pub fn get_lead_image(root: &Node, url: &Url) -> Option<String> {
+    for img in Util::get_elements_by_tag_name(root, "img") {
+        let dimension = |attribute: &str| {
+            img.get_attribute(attribute)
+                .and_then(|value| value.trim().trim_end_matches("px").parse::<u32>().ok())
+        };
+        let width = dimension("width");
+        let height = dimension("height");
+
+        let mut src = img.get_attribute("src");
+        let mut srcset_width = None;
+        if let Some(srcset) = img.get_attribute("srcset") {
+            for cap in SRC_SET_URL.captures_iter(&srcset) {
+                let candidate = cap.get(1).map_or("", |m| m.as_str());
+                let descriptor = cap.get(2).map_or("", |m| m.as_str()).trim();
+                let candidate_width = descriptor
+                    .strip_suffix('w')
+                    .and_then(|w| w.parse::<u32>().ok());
+
+                if candidate_width > srcset_width || src.is_none() {
+                    srcset_width = candidate_width.or(srcset_width);
+                    src = Some(candidate.trim_end_matches(',').to_string());
+                }
+            }
+        }
+
+        let src = match src {
+            Some(src) if !src.trim().is_empty() && !src.starts_with("data:") => src,
+            _ => continue,
+        };
+
+        let too_small = width.or(srcset_width).map(|w| w < MIN_LEAD_IMAGE_SIZE) == Some(true)
+            || height.map(|h| h < MIN_LEAD_IMAGE_SIZE) == Some(true);
+        if too_small {
+            continue;
+        }
+
+        if let Ok(src) = url.join(src.trim()) {
+            return Some(src.to_string());
+        }
+    }
+
+    None
 }
 
 fn get_meta(context: &Context, name: &str) -> Option<String> {
@@ -1477,24 +2394,43 @@ fn get_meta(context: &Context, name: &str) -> Option<String> {
     .ok()
 }
 
-pub fn post_process_page(node: &mut Node) -> Result<(), FullTextParserError> {
-    Util::clean_headers(node);
+// Exact match on a meta tag identified by name, property (OpenGraph) or itemprop.
+fn get_meta_content(context: &Context, name: &str) -> Option<String> {
+    Util::get_attribute(
+        context,
+        &format!(
+            "//meta[@name='{}' or @property='{}' or @itemprop='{}']",
+            name, name, name
+        ),
+        "content",
+    )
+    .ok()
+    .map(|content| content.trim().to_string())
+    .filter(|content| !content.is_empty())
+}
+
+pub fn post_process_page(
+    node: &mut Node,
+    options: &ExtractOptions,
+    trace: &mut Trace,
+) -> Result<(), FullTextParserError> {
+    Util::clean_headers(node, options, trace);
     Util::replace_schema_org_orbjects(node);
-    Util::clean_conditionally(node, "fieldset");
-    Util::clean_conditionally(node, "table");
-    Util::clean_conditionally(node, "ul");
-    Util::clean_conditionally(node, "div");
+    Util::clean_conditionally(node, "fieldset", options, trace);
+    Util::clean_conditionally(node, "table", options, trace);
+    Util::clean_conditionally(node, "ul", options, trace);
+    Util::clean_conditionally(node, "div", options, trace);
 
-    remove_share_elements(node);
+    remove_share_elements(node, trace);
     clean_attributes(node)?;
     remove_single_cell_tables(node);
     remove_extra_p_and_div(node);
-    remove_empty_nodes(node);
+    remove_empty_nodes(node, trace);
 
     Ok(())
 }
 
-fn remove_empty_nodes(root: &mut Node) {
+fn remove_empty_nodes(root: &mut Node, trace: &mut Trace) {
     let mut node_iter = Some(root.clone());
 
     while let Some(mut node) = node_iter {
@@ -1506,6 +2442,7 @@ fn remove_empty_nodes(root: &mut Node) {
         }
 
         if Util::is_element_without_children(&node) {
+            trace.remove(&node, "empty_node", &[]);
             node_iter = Util::remove_and_next(&mut node);
             continue;
         }
@@ -1513,7 +2450,7 @@ fn remove_empty_nodes(root: &mut Node) {
         node_iter = Util::next_node(&node, false);
     }
 }
-fn remove_share_elements(root: &mut Node) {
+fn remove_share_elements(root: &mut Node, trace: &mut Trace) {
     let mut node_iter = Some(root.clone());
 
     while let Some(mut node) = node_iter {
@@ -1526,6 +2463,7 @@ fn remove_share_elements(root: &mut Node) {
         if SHARE_ELEMENTS.is_match(&match_string)
             && node.get_content().len() < DEFAULT_CHAR_THRESHOLD
         {
+            trace.remove(&node, "share_element", &[]);
             node_iter = Util::remove_and_next(&mut node);
         } else {
             node_iter = Util::next_node(&node, false);
@@ -1534,6 +2472,57 @@ fn remove_share_elements(root: &mut Node) {
 }
 
 impl Article {
+    pub fn new(url: Url) -> Self {
+        Self {
+            title: None,
+            author: None,
+            url,
+            canonical_url: None,
+            date: None,
+            modified_date: None,
+            thumbnail_url: None,
+            excerpt: None,
+            site_name: None,
+            language: None,
+            detected_language: None,
+            direction: None,
+            stats: None,
+            json_ld: None,
+            document: None,
+            root_node: None,
+            trace: None,
+            annotated_html: None,
+        }
+    }
+
+    pub fn to_extracted(&self) -> Option<ExtractedArticle> {
+        let content = self.get_content()?;
+
+        Some(ExtractedArticle {
+            title: self.title.clone(),
+            byline: self.author.clone(),
+            authors: self
+                .author
+                .as_deref()
+                .map(Util::split_byline)
+                .unwrap_or_default(),
+            url: self.url.clone(),
+            canonical_url: self.canonical_url.clone(),
+            date: self.date,
+            modified_date: self.modified_date,
+            thumbnail_url: self.thumbnail_url.clone(),
+            excerpt: self.excerpt.clone(),
+            site_name: self.site_name.clone(),
+            language: self.language.clone(),
+            detected_language: self.detected_language.clone(),
+            direction: self.direction,
+            stats: self.stats,
+            content,
+            trace: self.trace.clone(),
+            annotated_html: self.annotated_html.clone(),
+        })
+    }
+
     pub fn get_content(&self) -> Option<String> {
         if let (Some(document), Some(root)) = (self.document.as_ref(), self.root_node.as_ref()) {
             Some(document.node_to_string(root))
@@ -1541,13 +2530,371 @@ impl Article {
             None
         }
     }
+
+    pub fn to_markdown(&self, link_style: LinkStyle) -> Option<String> {
+        // `root_node` belongs to the source document, which is gone by now
+        let root = self.document.as_ref()?.get_root_element()?;
+        Some(Markdown::from_node(&root, link_style))
+    }
+
+    pub fn chunks(&self, options: &ChunkOptions) -> Option<Vec<Chunk>> {
+        let root = self.document.as_ref()?.get_root_element()?;
+        Some(Chunker::chunk_node(&root, options))
+    }
+}
+
+impl ExtractedArticle {
+    pub fn to_markdown(&self, link_style: LinkStyle) -> Result<String, FullTextParserError> {
+        Markdown::from_html(&self.content, link_style)
+    }
+
+    pub fn chunks(&self, options: &ChunkOptions) -> Result<Vec<Chunk>, FullTextParserError> {
+        Chunker::chunk_html(&self.content, options)
+    }
 }
 
-#[derive(Debug, Clone)]
-pub struct ImageObject {
-    width: Option<u32>,
-    height: Option<u32>,
-    url: Option<Url>,
-    description: Option<String>,
-    name: Option<String>,
+#[cfg(test)]
+mod tests {
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+    use std::{fs, path::Path, thread};
+
+    use super::{
+        get_lead_image, ConfigCollection, ConfigEntry, ExtractOptions, Fetcher, HtmlEncoding,
+        Readability, TextDirection,
+    };
+    use url::Url;
+
+    // Serve the files of `resources/tests` over http on a random local port.
+    fn serve_fixtures() -> Url {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = Url::parse(&format!("http://{}/", listener.local_addr().unwrap())).unwrap();
+        let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("resources/tests");
+
+        thread::spawn(move || {
+            for mut stream in listener.incoming().flatten() {
+                let mut lines = BufReader::new(&stream).lines().map_while(Result::ok);
+                let request = lines.next().unwrap_or_default();
+                for _header in lines.by_ref().take_while(|line| !line.is_empty()) {}
+
+                let path = request.split(' ').nth(1).unwrap_or("/");
+                let response = match fs::read(root.join(path.trim_start_matches('/'))) {
+                    Ok(body) => [
+                        format!(
+                            "HTTP/1.1 200 OK\r\nContent-Type: text/html; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                            body.len()
+                        )
+                        .into_bytes(),
+                        body,
+                    ]
+                    .concat(),
+                    Err(_) => b"HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n".to_vec(),
+                };
+                _ = stream.write_all(&response);
+            }
+        });
+
+        url
+    }
+
+    #[tokio::test]
+    async fn extract_article() {
+        let html = include_str!("../../resources/tests/article/simple.html");
+        let url =
+            Url::parse("https://coastal.example/stories/lighthouse-keepers?ref=feed").unwrap();
+        let article =
+            Readability::extract_article(html, Some(url.clone()), &ExtractOptions::default())
+                .await
+                .unwrap();
+
+        assert_eq!(
+            article.title.as_deref(),
+            Some("Lighthouse keepers of the north coast")
+        );
+        assert_eq!(article.byline.as_deref(), Some("Jane Doe"));
+        assert_eq!(article.authors, vec!["Jane Doe"]);
+        assert_eq!(article.url, url);
+        assert_eq!(
+            article.canonical_url.as_ref().map(Url::as_str),
+            Some("https://coastal.example/stories/lighthouse-keepers")
+        );
+        assert_eq!(
+            article.date.map(|date| date.to_rfc3339()).as_deref(),
+            Some("2023-05-04T08:00:00+00:00")
+        );
+        assert_eq!(
+            article.excerpt.as_deref(),
+            Some("The last keepers remember the lamps.")
+        );
+        assert_eq!(article.site_name.as_deref(), Some("The Coastal Review"));
+        assert_eq!(article.language.as_deref(), Some("en"));
+        assert!(article.content.contains("trimming wicks"));
+        assert!(!article.content.contains("Copyright"));
+        assert!(!article.content.contains("Stories</a>"));
+    }
+
+    #[tokio::test]
+    async fn multi_page() {
+        let url = serve_fixtures().join("multipage/page1.html").unwrap();
+        let fetcher = Fetcher::with_client(reqwest::Client::builder().no_proxy().build().unwrap());
+        let extract = |single_page_link: Option<&str>, max_pages: usize| {
+            let mut configs = ConfigCollection::new();
+            configs.insert(
+                url.host_str().unwrap(),
+                ConfigEntry {
+                    single_page_link: single_page_link.map(String::from),
+                    next_page_link: Some("//a[@rel='next']".into()),
+                    ..ConfigEntry::default()
+                },
+            );
+            let options = ExtractOptions::builder()
+                .max_pages(max_pages)
+                .build()
+                .unwrap();
+            let url = url.clone();
+            let fetcher = &fetcher;
+            async move {
+                Readability::extract_url(url, &configs, fetcher, &options)
+                    .await
+                    .unwrap()
+                    .content
+            }
+        };
+
+        // page 3 links back to page 1, which ends the loop
+        let content = extract(None, 10).await;
+        for marker in ["PAGE-ONE-MARKER", "PAGE-TWO-MARKER", "PAGE-THREE-MARKER"] {
+            assert_eq!(content.matches(marker).count(), 1, "{marker}");
+        }
+        for page in 1..=3 {
+            assert!(content.contains(&format!(r#"id="readability-page-{page}""#)));
+        }
+        assert!(!content.contains("readability-page-4"));
+
+        let content = extract(None, 2).await;
+        assert!(content.contains("PAGE-TWO-MARKER"));
+        assert!(!content.contains("PAGE-THREE-MARKER"));
+
+        // the print view replaces the paginated article
+        let content = extract(Some("//a[@class='print']"), 10).await;
+        assert!(content.contains("PRINT-VIEW-MARKER"));
+        assert!(!content.contains("PAGE-ONE-MARKER"));
+
+        // and is skipped if it can't be downloaded
+        let content = extract(Some("//a[@class='pdf']"), 10).await;
+        assert!(content.contains("PAGE-THREE-MARKER"));
+    }
+
+    #[tokio::test]
+    async fn xpath_body() {
+        let html = include_str!("../../resources/tests/xpath_body/story.html");
+        let url = Url::parse("https://harbour.example/news/breakwater").unwrap();
+        let story = "rebuild the old breakwater";
+        let quote = "We have waited long enough";
+        let sidebar = "tide tables";
+
+        let extract = |site: &[&str], global: &[&str]| {
+            let config = |xpaths: &[&str]| ConfigEntry {
+                xpath_body: xpaths.iter().map(|xpath| xpath.to_string()).collect(),
+                ..ConfigEntry::default()
+            };
+            let mut configs = ConfigCollection::new();
+            configs.insert("harbour.example", config(site));
+            configs.insert("global", config(global));
+
+            let url = url.clone();
+            async move {
+                Readability::extract_with_configs(html, url, &configs, &ExtractOptions::default())
+                    .await
+                    .unwrap()
+                    .content
+            }
+        };
+
+        // the first xpath with a match wins, nested matches are only taken once
+        let content = extract(
+            &["//div[@id='missing']", "//div[contains(@class, 'story')]"],
+            &["//div[@id='tides']"],
+        )
+        .await;
+        assert!(content.contains(story));
+        assert_eq!(content.matches(quote).count(), 1);
+        assert!(!content.contains(sidebar));
+
+        // site config xpaths go before the global ones
+        let content = extract(&["//div[@id='missing']"], &["//div[@id='tides']"]).await;
+        assert!(content.contains(sidebar));
+        assert!(!content.contains(story));
+
+        // scoring takes over if no xpath matches
+        let content = extract(&["//div[@id='missing']"], &["//section"]).await;
+        assert!(content.contains(story));
+        assert!(content.contains(quote));
+    }
+
+    #[tokio::test]
+    async fn thumbnail() {
+        let url = Url::parse("https://example.com/news/story.html").unwrap();
+        let lead_image = |body: &str| {
+            let document =
+                HtmlEncoding::parse_utf8(&format!("<html><body>{body}</body></html>")).unwrap();
+            get_lead_image(&document.get_root_element().unwrap(), &url)
+        };
+
+        // icons and images below MIN_LEAD_IMAGE_SIZE are skipped
+        assert_eq!(
+            lead_image(
+                r#"<img src="/icon.png" width="32" height="32">
+                <img src="data:image/gif;base64,R0lGOD" width="600">
+                <img src="wide.jpg" width="600" height="100px">
+                <img src="lead.jpg" width="600" height="400">"#
+            )
+            .as_deref(),
+            Some("https://example.com/news/lead.jpg")
+        );
+        // the widest srcset candidate wins over src, its width counts as size
+        assert_eq!(
+            lead_image(
+                r#"<img src="small.jpg" srcset="/img/tiny-100.jpg 100w">
+                <img src="a.jpg" srcset="/img/a-320.jpg 320w, /img/a-1024.jpg 1024w, /img/a-640.jpg 640w">"#
+            )
+            .as_deref(),
+            Some("https://example.com/img/a-1024.jpg")
+        );
+        assert_eq!(lead_image(r#"<img src="/logo.png" width="120">"#), None);
+
+        let paragraph = "Lorem ipsum dolor sit amet, consectetur adipiscing elit, sed do eiusmod tempor incididunt ut labore et dolore magna aliqua. ";
+        let body = format!(
+            r#"<article><img src="/body.jpg" width="800" height="600">{}</article>"#,
+            format!("<p>{paragraph}</p>").repeat(6)
+        );
+        let head = r#"<meta name="twitter:image" content="/twitter.jpg">
+            <link rel="image_src" href="/image_src.jpg">"#;
+        let thumbnail = |head: &str| {
+            let html = format!("<html><head>{head}</head><body>{body}</body></html>");
+            let url = url.clone();
+            async move {
+                Readability::extract_article(&html, Some(url), &ExtractOptions::default())
+                    .await
+                    .unwrap()
+                    .thumbnail_url
+            }
+        };
+
+        // og:image goes before twitter:image, <link rel="image_src"> and the body
+        assert_eq!(
+            thumbnail(&format!(
+                r#"<meta property="og:image" content="og.jpg">{head}"#
+            ))
+            .await
+            .as_deref(),
+            Some("https://example.com/news/og.jpg")
+        );
+        assert_eq!(
+            thumbnail(head).await.as_deref(),
+            Some("https://example.com/twitter.jpg")
+        );
+        assert_eq!(
+            thumbnail("").await.as_deref(),
+            Some("https://example.com/body.jpg")
+        );
+    }
+
+    #[tokio::test]
+    async fn excerpt() {
+        let paragraph = "Lorem ipsum dolor sit amet, consectetur adipiscing elit, sed do eiusmod tempor incididunt ut labore et dolore magna aliqua. ";
+        let body = format!(
+            "<p>Short intro.</p>{}",
+            format!("<p>{paragraph}</p>").repeat(6)
+        );
+
+        let html = format!(
+            r#"<html><head><meta property="og:description" content="Fish &amp;amp; chips,
+                explained"></head><body><article>{body}</article></body></html>"#
+        );
+        let article = Readability::extract_article(&html, None, &ExtractOptions::default())
+            .await
+            .unwrap();
+        assert_eq!(article.excerpt.as_deref(), Some("Fish & chips, explained"));
+
+        let html = format!(
+            r#"<html><head><script type="application/ld+json">
+                {{"@type": "NewsArticle", "description": "From JSON-LD"}}
+            </script></head><body><article>{body}</article></body></html>"#
+        );
+        let article = Readability::extract_article(&html, None, &ExtractOptions::default())
+            .await
+            .unwrap();
+        assert_eq!(article.excerpt.as_deref(), Some("From JSON-LD"));
+
+        let html = format!("<html><body><article>{body}</article></body></html>");
+        let article = Readability::extract_article(&html, None, &ExtractOptions::default())
+            .await
+            .unwrap();
+        assert_eq!(article.excerpt.as_deref(), Some(paragraph.trim()));
+    }
+
+    #[tokio::test]
+    async fn language_and_direction() {
+        let paragraph = "قالت اللجنة يوم الثلاثاء إنها لم تتمكن من تأكيد التقرير الذي انتشر على نطاق واسع في وسائل التواصل الاجتماعي، وأضافت أن التحقيق مستمر. ";
+        let story = format!("<p>{paragraph}</p>").repeat(4);
+        let html = format!(
+            r#"<html><head><meta http-equiv="Content-Language" content="ar_EG"></head>
+            <body><div dir="rtl"><div id="story">{story}</div></div></body></html>"#
+        );
+
+        let options = ExtractOptions::builder()
+            .detect_language(true)
+            .build()
+            .unwrap();
+        let article = Readability::extract_article(&html, None, &options)
+            .await
+            .unwrap();
+        assert_eq!(article.language.as_deref(), Some("ar-EG"));
+        assert_eq!(article.detected_language.as_deref(), Some("ar"));
+        assert_eq!(article.direction, Some(TextDirection::Rtl));
+
+        let html = html.replace(r#" dir="rtl""#, "");
+        let article = Readability::extract_article(&html, None, &ExtractOptions::default())
+            .await
+            .unwrap();
+        assert_eq!(article.detected_language, None);
+        assert_eq!(article.direction, Some(TextDirection::Rtl));
+    }
+
+    #[tokio::test]
+    async fn json_ld_article_body() {
+        let html = r#"<html><head><script type="application/ld+json">
+            {"@type": "NewsArticle", "headline": "Night ferry",
+             "articleBody": "The night ferry leaves the harbour at eleven and reaches the island before dawn, weather permitting.\nTickets are sold on board, and the crossing takes about five hours in calm seas."}
+        </script></head><body><article><p>Subscribe to read the night ferry story.</p></article></body></html>"#;
+        let url = Url::parse("https://ferry.example/night").unwrap();
+
+        // scoring finds less than `char_threshold`, the longer articleBody wins
+        let article =
+            Readability::extract_article(html, Some(url.clone()), &ExtractOptions::default())
+                .await
+                .unwrap();
+        assert!(article
+            .content
+            .contains("<p>The night ferry leaves the harbour at eleven"));
+        assert!(article.content.contains("<p>Tickets are sold on board"));
+        assert!(!article.content.contains("Subscribe"));
+
+        // a body extracted with the site config is kept
+        let mut configs = ConfigCollection::new();
+        configs.insert(
+            "ferry.example",
+            ConfigEntry {
+                xpath_body: vec!["//article".into()],
+                ..ConfigEntry::default()
+            },
+        );
+        let article =
+            Readability::extract_with_configs(html, url, &configs, &ExtractOptions::default())
+                .await
+                .unwrap();
+        assert!(article.content.contains("Subscribe"));
+        assert!(!article.content.contains("Tickets are sold"));
+    }
 }
@@ -0,0 +1,364 @@
+use libxml::tree::Node;
+use regex::{Regex, RegexBuilder};
+
+use super::constants::{
+    CLASS_WEIGHT, DEFAULT_CHAR_THRESHOLD, DEFAULT_MAX_PAGES, DEFAULT_TOP_CANDIDATES,
+    MINIMUM_TOPCANDIDATES, NEGATIVE, POSITIVE, UNLIELY_CANDIDATES,
+};
+
+// Tuning of the extraction. The defaults match the behaviour of Mozilla's
+// Readability.js, use `ExtractOptions::builder()` to change them.
+#[derive(Clone, Debug)]
+pub struct ExtractOptions {
+    // minimum number of characters an article needs, otherwise extraction is
+    // retried with less aggressive cleaning
+    pub(crate) char_threshold: usize,
+    // number of best scored candidates considered for the top candidate
+    pub(crate) top_candidates: usize,
+    // number of close candidates that have to share an ancestor to make it the top candidate
+    pub(crate) min_top_candidates: usize,
+    // candidates scoring at least this ratio of the top candidate count as close
+    pub(crate) candidate_score_ratio: f64,
+    // minimum score of a sibling of the top candidate to be included
+    pub(crate) sibling_score_threshold: f64,
+    pub(crate) class_weight: i64,
+    pub(crate) weigh_classes: bool,
+    pub(crate) strip_unlikely: bool,
+    pub(crate) clean_conditionally: bool,
+    pub(crate) keep_images: bool,
+    pub(crate) keep_tables: bool,
+    pub(crate) keep_videos: bool,
+    // maximum link density of an element without a positive class weight
+    pub(crate) link_density: f64,
+    // maximum link density of an element with a positive class weight
+    pub(crate) weighted_link_density: f64,
+    pub(crate) positive: Option<Regex>,
+    pub(crate) negative: Option<Regex>,
+    pub(crate) unlikely: Option<Regex>,
+    pub(crate) max_pages: usize,
+    // record a `Trace` of all decisions, see `ExtractedArticle::trace`
+    pub(crate) trace: bool,
+    // render the source document annotated with the decisions, see
+    // `ExtractedArticle::annotated_html`
+    pub(crate) annotate: bool,
+    // guess the language from the extracted text, see `LanguageDetector`
+    pub(crate) detect_language: bool,
+}
+
+impl Default for ExtractOptions {
+    fn default() -> Self {
+        Self {
+            char_threshold: DEFAULT_CHAR_THRESHOLD,
+            top_candidates: DEFAULT_TOP_CANDIDATES,
+            min_top_candidates: MINIMUM_TOPCANDIDATES,
+            candidate_score_ratio: 0.75,
+            sibling_score_threshold: 8.0,
+            class_weight: CLASS_WEIGHT,
+            weigh_classes: true,
+            strip_unlikely: true,
+            clean_conditionally: true,
+            keep_images: true,
+            keep_tables: true,
+            keep_videos: true,
+            link_density: 0.2,
+            weighted_link_density: 0.5,
+            positive: None,
+            negative: None,
+            unlikely: None,
+            max_pages: DEFAULT_MAX_PAGES,
+            trace: false,
+            annotate: false,
+            detect_language: false,
+        }
+    }
+}
+
+impl ExtractOptions {
+    pub fn builder() -> ExtractOptionsBuilder {
+        ExtractOptionsBuilder::default()
+    }
+
+    pub fn char_threshold(&self) -> usize {
+        self.char_threshold
+    }
+
+    pub fn max_pages(&self) -> usize {
+        self.max_pages
+    }
+
+    pub(crate) fn is_positive(&self, value: &str) -> bool {
+        POSITIVE.is_match(value) || self.positive.as_ref().is_some_and(|re| re.is_match(value))
+    }
+
+    pub(crate) fn is_negative(&self, value: &str) -> bool {
+        NEGATIVE.is_match(value) || self.negative.as_ref().is_some_and(|re| re.is_match(value))
+    }
+
+    pub(crate) fn is_unlikely(&self, value: &str) -> bool {
+        UNLIELY_CANDIDATES.is_match(value)
+            || self.unlikely.as_ref().is_some_and(|re| re.is_match(value))
+    }
+
+    // Score the class and id of a node: negative names like "comment" or
+    // "sidebar" subtract `class_weight`, positive ones like "article" add it.
+    pub(crate) fn class_weight(&self, node: &Node) -> i64 {
+        let mut weight = 0;
+
+        for value in [node.get_property("class"), node.get_property("id")]
+            .into_iter()
+            .flatten()
+        {
+            if self.is_negative(&value) {
+                weight -= self.class_weight;
+            }
+
+            if self.is_positive(&value) {
+                weight += self.class_weight;
+            }
+        }
+
+        weight
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct ExtractOptionsBuilder {
+    options: ExtractOptions,
+    positive: Option<String>,
+    negative: Option<String>,
+    unlikely: Option<String>,
+}
+
+impl ExtractOptionsBuilder {
+    pub fn char_threshold(mut self, char_threshold: usize) -> Self {
+        self.options.char_threshold = char_threshold;
+        self
+    }
+
+    pub fn top_candidates(mut self, top_candidates: usize) -> Self {
+        self.options.top_candidates = top_candidates.max(1);
+        self
+    }
+
+    pub fn min_top_candidates(mut self, min_top_candidates: usize) -> Self {
+        self.options.min_top_candidates = min_top_candidates;
+        self
+    }
+
+    pub fn candidate_score_ratio(mut self, ratio: f64) -> Self {
+        self.options.candidate_score_ratio = ratio;
+        self
+    }
+
+    pub fn sibling_score_threshold(mut self, threshold: f64) -> Self {
+        self.options.sibling_score_threshold = threshold;
+        self
+    }
+
+    pub fn class_weight(mut self, class_weight: i64) -> Self {
+        self.options.class_weight = class_weight;
+        self
+    }
+
+    pub fn weigh_classes(mut self, weigh_classes: bool) -> Self {
+        self.options.weigh_classes = weigh_classes;
+        self
+    }
+
+    pub fn strip_unlikely(mut self, strip_unlikely: bool) -> Self {
+        self.options.strip_unlikely = strip_unlikely;
+        self
+    }
+
+    pub fn clean_conditionally(mut self, clean_conditionally: bool) -> Self {
+        self.options.clean_conditionally = clean_conditionally;
+        self
+    }
+
+    pub fn keep_images(mut self, keep_images: bool) -> Self {
+        self.options.keep_images = keep_images;
+        self
+    }
+
+    pub fn keep_tables(mut self, keep_tables: bool) -> Self {
+        self.options.keep_tables = keep_tables;
+        self
+    }
+
+    pub fn keep_videos(mut self, keep_videos: bool) -> Self {
+        self.options.keep_videos = keep_videos;
+        self
+    }
+
+    pub fn link_density(mut self, link_density: f64) -> Self {
+        self.options.link_density = link_density;
+        self
+    }
+
+    pub fn weighted_link_density(mut self, link_density: f64) -> Self {
+        self.options.weighted_link_density = link_density;
+        self
+    }
+
+    // Additional class/id pattern that counts as positive, on top of `constants::POSITIVE`.
+    pub fn positive_pattern(mut self, pattern: &str) -> Self {
+        self.positive = Some(pattern.into());
+        self
+    }
+
+    // Additional class/id pattern that counts as negative, on top of `constants::NEGATIVE`.
+    pub fn negative_pattern(mut self, pattern: &str) -> Self {
+        self.negative = Some(pattern.into());
+        self
+    }
+
+    // Additional class/id pattern of elements that are removed before scoring,
+    // on top of `constants::UNLIELY_CANDIDATES`.
+    pub fn unlikely_pattern(mut self, pattern: &str) -> Self {
+        self.unlikely = Some(pattern.into());
+        self
+    }
+
+    pub fn max_pages(mut self, max_pages: usize) -> Self {
+        self.options.max_pages = max_pages.max(1);
+        self
+    }
+
+    pub fn trace(mut self, trace: bool) -> Self {
+        self.options.trace = trace;
+        self
+    }
+
+    pub fn annotate(mut self, annotate: bool) -> Self {
+        self.options.annotate = annotate;
+        self
+    }
+
+    pub fn detect_language(mut self, detect_language: bool) -> Self {
+        self.options.detect_language = detect_language;
+        self
+    }
+
+    pub fn build(self) -> Result<ExtractOptions, regex::Error> {
+        let compile = |pattern: Option<String>| {
+            pattern
+                .map(|pattern| RegexBuilder::new(&pattern).case_insensitive(true).build())
+                .transpose()
+        };
+
+        Ok(ExtractOptions {
+            positive: compile(self.positive)?,
+            negative: compile(self.negative)?,
+            unlikely: compile(self.unlikely)?,
+            ..self.options
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use libxml::parser::Parser;
+
+    use super::{ExtractOptions, ExtractOptionsBuilder};
+    use crate::readability::Readability;
+
+    const ARTICLE: &str = include_str!("../../../resources/tests/options/article.html");
+
+    async fn extract(options: ExtractOptionsBuilder) -> String {
+        Readability::extract(ARTICLE, None, &options.build().unwrap())
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn keep_media() {
+        let content = extract(ExtractOptions::builder()).await;
+        for tag in ["<figure", "<img", "<table", "<video"] {
+            assert!(content.contains(tag), "{tag}");
+        }
+
+        let content = extract(ExtractOptions::builder().keep_images(false)).await;
+        assert!(!content.contains("<img"));
+        assert!(!content.contains("<figure"));
+        assert!(content.contains("<table"));
+
+        let content = extract(ExtractOptions::builder().keep_tables(false)).await;
+        assert!(!content.contains("<table"));
+        assert!(!content.contains("Bramley"));
+        assert!(content.contains("<img"));
+
+        let content = extract(ExtractOptions::builder().keep_videos(false)).await;
+        assert!(!content.contains("<video"));
+        assert!(content.contains("<table"));
+    }
+
+    #[tokio::test]
+    async fn class_pattern_extraction() {
+        let content = extract(ExtractOptions::builder()).await;
+        assert!(content.contains("TEASER-MARKER"));
+        assert!(content.contains("KICKER-MARKER"));
+        // "widget" is negative, so the block is cleaned away
+        assert!(!content.contains("LEDE-MARKER"));
+
+        let content = extract(ExtractOptions::builder().unlikely_pattern("teaser")).await;
+        assert!(!content.contains("TEASER-MARKER"));
+
+        let content = extract(ExtractOptions::builder().negative_pattern("kicker")).await;
+        assert!(!content.contains("KICKER-MARKER"));
+
+        let content = extract(ExtractOptions::builder().positive_pattern("lede")).await;
+        assert!(content.contains("LEDE-MARKER"));
+    }
+
+    #[tokio::test]
+    async fn char_threshold() {
+        let html = r#"<html><body><div class="story">
+            <p>The ferry to the island runs twice a day in summer and once a day in winter, weather permitting, from the old pier below the harbour office.</p>
+            <p>Tickets are sold on board and bicycles travel for free.</p>
+            <div class="sidebar"><p>SIDEBAR-MARKER: timetables for the other ferries of the coast can be found at the harbour office.</p></div>
+        </div></body></html>"#;
+        let extract = |char_threshold| async move {
+            let options = ExtractOptions::builder()
+                .char_threshold(char_threshold)
+                .build()
+                .unwrap();
+            Readability::extract(html, None, &options).await.unwrap()
+        };
+
+        // too short for the default, the retries keep the unlikely sidebar
+        assert!(extract(500).await.contains("SIDEBAR-MARKER"));
+        // long enough for the first pass, which strips it
+        let content = extract(100).await;
+        assert!(content.contains("The ferry to the island"));
+        assert!(!content.contains("SIDEBAR-MARKER"));
+    }
+
+    #[test]
+    fn custom_class_patterns() {
+        let html = r#"<html><body><div class="lede" id="sidebar">Text</div></body></html>"#;
+        let document = Parser::default_html().parse_string(html).unwrap();
+        let mut xpath_ctx = libxml::xpath::Context::new(&document).unwrap();
+        let node = xpath_ctx.findnodes("//div", None).unwrap().remove(0);
+
+        assert_eq!(ExtractOptions::default().class_weight(&node), -25);
+
+        let options = ExtractOptions::builder()
+            .positive_pattern("lede")
+            .class_weight(10)
+            .build()
+            .unwrap();
+        assert_eq!(options.class_weight(&node), 0);
+
+        let options = ExtractOptions::builder()
+            .unlikely_pattern("teaser")
+            .build()
+            .unwrap();
+        assert!(options.is_unlikely("TEASER"));
+        assert!(options.is_unlikely("disqus"));
+
+        assert!(ExtractOptions::builder()
+            .negative_pattern("(")
+            .build()
+            .is_err());
+    }
+}
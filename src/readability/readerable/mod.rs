@@ -0,0 +1,108 @@
+use libxml::tree::{Document, Node};
+use libxml::xpath::Context;
+
+use super::constants::{OKAY_MAYBE_ITS_A_CANDIDATE, UNLIELY_CANDIDATES};
+use super::util::Util;
+use super::FullTextParserError;
+
+// Thresholds of the readerable check. The defaults are the ones of Mozilla's
+// `isProbablyReaderable`.
+#[derive(Clone, Debug)]
+pub struct ReaderableOptions {
+    // score a page needs to be considered readerable
+    pub min_score: f64,
+    // paragraphs with less characters don't count
+    pub min_content_length: usize,
+}
+
+impl Default for ReaderableOptions {
+    fn default() -> Self {
+        Self {
+            min_score: 20.0,
+            min_content_length: 140,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Readerable {
+    pub score: f64,
+    pub readerable: bool,
+}
+
+impl Readerable {
+    // Cheap estimate whether a document contains an article, without running
+    // the extraction. Every visible and likely `p`, `pre` and `article` node
+    // (and `div` with `br` separated text) longer than `min_content_length`
+    // adds the square root of the excess characters to the score.
+    pub fn check(
+        document: &Document,
+        options: &ReaderableOptions,
+    ) -> Result<Self, FullTextParserError> {
+        let context = Context::new(document).map_err(|()| {
+            log::error!("Creating xpath context failed for readerable check");
+            FullTextParserError::Xml
+        })?;
+        let nodes = Util::evaluate_xpath(&context, "//p | //pre | //article | //div[br]", false)?;
+
+        let score = nodes
+            .iter()
+            .filter(|node| Util::is_probably_visible(node))
+            .filter(|node| !Self::is_unlikely(node))
+            .filter(|node| {
+                !Util::has_ancestor_tag(node, "li", Some(u64::MAX), None::<fn(&Node) -> bool>)
+            })
+            .map(|node| node.get_content().trim().chars().count())
+            .filter(|length| *length >= options.min_content_length)
+            .map(|length| ((length - options.min_content_length) as f64).sqrt())
+            .sum::<f64>();
+
+        Ok(Self {
+            score,
+            readerable: score > options.min_score,
+        })
+    }
+
+    fn is_unlikely(node: &Node) -> bool {
+        let match_string = format!(
+            "{} {}",
+            node.get_attribute("class").unwrap_or_default(),
+            node.get_attribute("id").unwrap_or_default()
+        );
+
+        UNLIELY_CANDIDATES.is_match(&match_string)
+            && !OKAY_MAYBE_ITS_A_CANDIDATE.is_match(&match_string)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ReaderableOptions;
+    use crate::readability::Readability;
+
+    #[test]
+    fn readerable() {
+        let paragraph = "Lorem ipsum dolor sit amet, consectetur adipiscing elit, sed do eiusmod tempor incididunt ut labore et dolore magna aliqua. Ut enim ad minim veniam.";
+        let options = ReaderableOptions::default();
+
+        let article = format!(
+            "<html><body><article><p>{paragraph}</p><p>{paragraph}</p><p>{paragraph}</p></article></body></html>"
+        );
+        let result = Readability::is_probably_readerable(&article, &options).unwrap();
+        assert!(result.readerable);
+        assert!(result.score > options.min_score);
+
+        // teasers in lists, hidden text and sidebars don't count
+        let index = format!(
+            r#"<html><body>
+                <ul><li><p>{paragraph}</p></li><li><p>{paragraph}</p></li></ul>
+                <p hidden>{paragraph}</p>
+                <p class="sidebar">{paragraph}</p>
+                <p>Sign in to continue reading.</p>
+            </body></html>"#
+        );
+        let result = Readability::is_probably_readerable(&index, &options).unwrap();
+        assert!(!result.readerable);
+        assert_eq!(result.score, 0.0);
+    }
+}
@@ -0,0 +1,245 @@
+use std::time::Duration;
+
+use headless_chrome::browser::tab::Tab;
+use headless_chrome::protocol::cdp::Emulation::SetDeviceMetricsOverride;
+use headless_chrome::types::PrintToPdfOptions;
+use url::Url;
+
+use super::config::ConfigCollection;
+use super::consent::ConsentHandler;
+use super::expand::ExpandOptions;
+use super::wait::Wait;
+
+// Size of the page layout. Responsive sites pick their layout by width and
+// the `mobile` flag, which also enables the mobile viewport meta tag.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Viewport {
+    pub width: u32,
+    pub height: u32,
+    pub device_scale_factor: f64,
+    pub mobile: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Device {
+    Desktop,
+    Ipad,
+    Iphone,
+    Android,
+}
+
+impl Device {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.trim().to_lowercase().as_str() {
+            "desktop" => Some(Self::Desktop),
+            "ipad" => Some(Self::Ipad),
+            "iphone" => Some(Self::Iphone),
+            "android" => Some(Self::Android),
+            _ => None,
+        }
+    }
+
+    pub fn viewport(self) -> Viewport {
+        let (width, height, device_scale_factor, mobile) = match self {
+            Self::Desktop => (1366, 900, 1.0, false),
+            Self::Ipad => (820, 1180, 2.0, true),
+            Self::Iphone => (390, 844, 3.0, true),
+            Self::Android => (412, 915, 2.625, true),
+        };
+        Viewport {
+            width,
+            height,
+            device_scale_factor,
+            mobile,
+        }
+    }
+
+    pub fn user_agent(self) -> &'static str {
+        match self {
+            Self::Desktop => "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Safari/537.36",
+            Self::Ipad => "Mozilla/5.0 (iPad; CPU OS 17_2 like Mac OS X) AppleWebKit/605.1.15 (KHTML, like Gecko) Version/17.2 Mobile/15E148 Safari/604.1",
+            Self::Iphone => "Mozilla/5.0 (iPhone; CPU iPhone OS 17_2 like Mac OS X) AppleWebKit/605.1.15 (KHTML, like Gecko) Version/17.2 Mobile/15E148 Safari/604.1",
+            Self::Android => "Mozilla/5.0 (Linux; Android 14; Pixel 7) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Mobile Safari/537.36",
+        }
+    }
+}
+
+// How a page is rendered and printed by the headless browser.
+#[derive(Clone, Debug)]
+pub struct RenderOptions {
+    pub viewport: Viewport,
+    // user agent of the browser if `None`
+    pub user_agent: Option<String>,
+    // zoom of the printed page
+    pub scale: f64,
+    // width and height of the paper in inches
+    pub paper_size: (f64, f64),
+    // margin on all sides in inches
+    pub margin: f64,
+    pub landscape: bool,
+    // pages to print like "1-2, 5", all if `None`
+    pub page_ranges: Option<String>,
+    pub print_background: bool,
+    // how long navigation and other browser calls may take
+    pub timeout: Duration,
+    // conditions the page has to reach after navigation, checked in order
+    pub wait: Vec<Wait>,
+    // consent banners to dismiss after the wait conditions are met
+    pub consent: Option<ConsentHandler>,
+    // scrolling and clicking once the page is free of consent banners
+    pub expand: Option<ExpandOptions>,
+}
+
+impl Default for RenderOptions {
+    fn default() -> Self {
+        Self {
+            // portrait iPad resolution, persuades responsive sites to send less
+            // non-essential data and show the central content with less clutter
+            viewport: Viewport {
+                width: 820,
+                height: 1180,
+                device_scale_factor: 1.0,
+                mobile: false,
+            },
+            user_agent: None,
+            scale: 0.5,
+            paper_size: (11.0, 17.0),
+            margin: 0.1,
+            landscape: false,
+            page_ranges: None,
+            print_background: false,
+            timeout: Duration::from_secs(20),
+            wait: Vec::new(),
+            consent: None,
+            expand: None,
+        }
+    }
+}
+
+impl RenderOptions {
+    // Viewport and user agent of `device`, everything else default.
+    pub fn for_device(device: Device) -> Self {
+        Self {
+            viewport: device.viewport(),
+            user_agent: Some(device.user_agent().to_owned()),
+            ..Self::default()
+        }
+    }
+
+    // Also wait for the content named by the site config `configs` has for the
+    // host of `url`, see `Wait::for_config`. Nothing changes without one.
+    pub fn wait_for_config(&mut self, configs: &ConfigCollection, url: &Url, timeout: Duration) {
+        if let Some(wait) = configs
+            .get(url)
+            .and_then(|config| Wait::for_config(config, timeout))
+        {
+            self.wait.push(wait);
+        }
+    }
+
+    // Set up a tab before navigating. Tabs are reused, so every setting is
+    // applied each time, `default_user_agent` undoes an earlier override.
+    pub(crate) fn apply(&self, tab: &Tab, default_user_agent: &str) -> anyhow::Result<()> {
+        tab.set_default_timeout(self.timeout);
+        tab.call_method(SetDeviceMetricsOverride {
+            width: self.viewport.width,
+            height: self.viewport.height,
+            device_scale_factor: self.viewport.device_scale_factor,
+            mobile: self.viewport.mobile,
+            scale: None,
+            screen_width: None,
+            screen_height: None,
+            position_x: None,
+            position_y: None,
+            dont_set_visible_size: None,
+            screen_orientation: None,
+            viewport: None,
+            display_feature: None,
+            device_posture: None,
+        })?;
+        tab.set_user_agent(
+            self.user_agent.as_deref().unwrap_or(default_user_agent),
+            None,
+            None,
+        )?;
+        Ok(())
+    }
+
+    pub(crate) fn pdf_options(&self) -> PrintToPdfOptions {
+        PrintToPdfOptions {
+            landscape: Some(self.landscape),
+            display_header_footer: Some(false),
+            print_background: Some(self.print_background),
+            scale: Some(self.scale),
+            paper_width: Some(self.paper_size.0),
+            paper_height: Some(self.paper_size.1),
+            margin_top: Some(self.margin),
+            margin_bottom: Some(self.margin),
+            margin_left: Some(self.margin),
+            margin_right: Some(self.margin),
+            page_ranges: self.page_ranges.clone(),
+            ignore_invalid_page_ranges: Some(true),
+            prefer_css_page_size: Some(false),
+            transfer_mode: None,
+            ..Default::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use url::Url;
+
+    use super::{Device, RenderOptions};
+    use crate::readability::{ConfigCollection, ConfigEntry, Wait, WaitCondition};
+
+    #[test]
+    fn render_options() {
+        let options = RenderOptions::for_device(Device::parse("iPhone").unwrap());
+        assert_eq!(options.viewport.width, 390);
+        assert!(options.viewport.mobile);
+        assert!(options.user_agent.as_deref().unwrap().contains("iPhone"));
+
+        let options = RenderOptions {
+            page_ranges: Some("1-2".to_owned()),
+            print_background: true,
+            ..RenderOptions::default()
+        };
+        let pdf_options = options.pdf_options();
+        assert_eq!(pdf_options.paper_width, Some(11.0));
+        assert_eq!(pdf_options.margin_left, Some(0.1));
+        assert_eq!(pdf_options.page_ranges.as_deref(), Some("1-2"));
+        assert_eq!(pdf_options.print_background, Some(true));
+        assert_eq!(Device::parse("fridge"), None);
+    }
+
+    #[test]
+    fn wait_for_config() {
+        let mut configs = ConfigCollection::new();
+        configs.insert(
+            "example.com",
+            ConfigEntry {
+                xpath_wait: vec!["//article".to_owned()],
+                ..ConfigEntry::default()
+            },
+        );
+        let timeout = Duration::from_secs(5);
+
+        let mut options = RenderOptions::default();
+        let url = Url::parse("https://www.example.com/story").unwrap();
+        options.wait_for_config(&configs, &url, timeout);
+        assert_eq!(
+            options.wait,
+            vec![Wait::new(
+                WaitCondition::XPath("//article".to_owned()),
+                timeout
+            )]
+        );
+
+        let url = Url::parse("https://example.org/story").unwrap();
+        options.wait_for_config(&configs, &url, timeout);
+        assert_eq!(options.wait.len(), 1);
+    }
+}
@@ -0,0 +1,217 @@
+use std::path::PathBuf;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::time::Duration;
+
+use headless_chrome::browser::tab::Tab;
+use headless_chrome::{Browser, LaunchOptions};
+
+use super::render::RenderOptions;
+
+#[derive(Clone, Debug)]
+pub struct SessionOptions {
+    // tabs open at the same time, further captures wait for a free one
+    pub max_tabs: usize,
+    // size of the browser window, pages are laid out in the viewport of
+    // their `RenderOptions`
+    pub window_size: (u32, u32),
+    // Chrome or Chromium binary, detected if `None`
+    pub chrome_path: Option<PathBuf>,
+    // the browser shuts down after being idle for this long, the next capture
+    // launches a new one
+    pub idle_timeout: Duration,
+}
+
+impl Default for SessionOptions {
+    fn default() -> Self {
+        Self {
+            max_tabs: 4,
+            window_size: (820, 1180),
+            chrome_path: None,
+            idle_timeout: Duration::from_secs(600),
+        }
+    }
+}
+
+// Html and pdf of a page, taken after the same navigation.
+#[derive(Clone, Debug)]
+pub struct Capture {
+    pub html: String,
+    pub pdf: Vec<u8>,
+}
+
+// A long lived headless browser. Captures run in tabs of a bounded pool:
+// tabs are reused between captures and a tab that failed or crashed is
+// closed and replaced by a fresh one. A browser that shut down or crashed is
+// relaunched. All methods block while Chrome works.
+pub struct BrowserSession {
+    browser: Mutex<Browser>,
+    default_user_agent: String,
+    options: SessionOptions,
+    pool: Mutex<TabPool>,
+    released: Condvar,
+}
+
+#[derive(Default)]
+struct TabPool {
+    idle: Vec<Arc<Tab>>,
+    // idle tabs plus the ones currently in use
+    open: usize,
+}
+
+impl BrowserSession {
+    pub fn new(options: SessionOptions) -> anyhow::Result<Self> {
+        let browser = launch(&options)?;
+        let default_user_agent = browser.get_version()?.user_agent;
+
+        Ok(Self {
+            browser: Mutex::new(browser),
+            default_user_agent,
+            options,
+            pool: Mutex::new(TabPool::default()),
+            released: Condvar::new(),
+        })
+    }
+
+    pub fn html(&self, url: &str, options: &RenderOptions) -> anyhow::Result<String> {
+        self.with_tab(options, |tab| {
+            navigate(tab, url, options)?;
+            tab.get_content()
+        })
+    }
+
+    pub fn pdf(&self, url: &str, options: &RenderOptions) -> anyhow::Result<Vec<u8>> {
+        self.with_tab(options, |tab| {
+            navigate(tab, url, options)?;
+            tab.print_to_pdf(Some(options.pdf_options()))
+        })
+    }
+
+    pub fn capture(&self, url: &str, options: &RenderOptions) -> anyhow::Result<Capture> {
+        self.with_tab(options, |tab| {
+            navigate(tab, url, options)?;
+            Ok(Capture {
+                html: tab.get_content()?,
+                pdf: tab.print_to_pdf(Some(options.pdf_options()))?,
+            })
+        })
+    }
+
+    // Run `work` in a tab of the pool set up for `options`. The tab is only
+    // reused if `work` succeeded, after an error its state is unknown.
+    fn with_tab<T>(
+        &self,
+        options: &RenderOptions,
+        work: impl FnOnce(&Tab) -> anyhow::Result<T>,
+    ) -> anyhow::Result<T> {
+        let tab = self.acquire()?;
+        let result = options
+            .apply(&tab, &self.default_user_agent)
+            .and_then(|()| work(&tab));
+        let reusable = result.is_ok() && tab.navigate_to("about:blank").is_ok();
+        self.release(tab, reusable);
+        result
+    }
+
+    // Tabs are checked and opened without holding the pool lock, a hung
+    // renderer must not block the other captures.
+    fn acquire(&self) -> anyhow::Result<Arc<Tab>> {
+        let mut pool = self.lock_pool()?;
+        loop {
+            if let Some(tab) = pool.idle.pop() {
+                drop(pool);
+                // the renderer of an idle tab may have crashed in the meantime
+                if tab.get_target_info().is_ok() {
+                    return Ok(tab);
+                }
+                log::debug!("Replacing crashed tab");
+                self.discard(tab);
+                pool = self.lock_pool()?;
+                continue;
+            }
+
+            if pool.open < self.options.max_tabs.max(1) {
+                pool.open += 1;
+                drop(pool);
+                return self
+                    .browser()
+                    .and_then(|browser| browser.new_tab())
+                    .inspect_err(|_| {
+                        if let Ok(mut pool) = self.pool.lock() {
+                            pool.open -= 1;
+                        }
+                        self.released.notify_one();
+                    });
+            }
+
+            pool = self
+                .released
+                .wait(pool)
+                .map_err(|_| anyhow::anyhow!("tab pool poisoned"))?;
+        }
+    }
+
+    fn lock_pool(&self) -> anyhow::Result<MutexGuard<'_, TabPool>> {
+        self.pool
+            .lock()
+            .map_err(|_| anyhow::anyhow!("tab pool poisoned"))
+    }
+
+    // The running browser, relaunched if it is gone. Tabs of the old browser
+    // fail the check in `acquire` and are replaced.
+    fn browser(&self) -> anyhow::Result<Browser> {
+        let mut browser = self
+            .browser
+            .lock()
+            .map_err(|_| anyhow::anyhow!("browser poisoned"))?;
+        if browser.get_version().is_err() {
+            log::debug!("Relaunching browser");
+            *browser = launch(&self.options)?;
+        }
+        Ok(browser.clone())
+    }
+
+    fn release(&self, tab: Arc<Tab>, reusable: bool) {
+        if !reusable {
+            self.discard(tab);
+            return;
+        }
+        if let Ok(mut pool) = self.pool.lock() {
+            pool.idle.push(tab);
+        }
+        self.released.notify_one();
+    }
+
+    // Close a tab that is not coming back to the pool and free its slot.
+    fn discard(&self, tab: Arc<Tab>) {
+        _ = tab.close_target();
+        if let Ok(mut pool) = self.pool.lock() {
+            pool.open -= 1;
+        }
+        self.released.notify_one();
+    }
+}
+
+fn launch(options: &SessionOptions) -> anyhow::Result<Browser> {
+    Browser::new(LaunchOptions {
+        headless: true,
+        window_size: Some(options.window_size),
+        path: options.chrome_path.clone(),
+        idle_browser_timeout: options.idle_timeout,
+        ..Default::default()
+    })
+}
+
+fn navigate(tab: &Tab, url: &str, options: &RenderOptions) -> anyhow::Result<()> {
+    tab.navigate_to(url)?;
+    tab.wait_until_navigated()?;
+    for wait in &options.wait {
+        wait.wait(tab)?;
+    }
+    if let Some(consent) = &options.consent {
+        consent.handle(tab)?;
+    }
+    if let Some(expand) = &options.expand {
+        expand.expand(tab)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,134 @@
+use std::time::Duration;
+
+use libxml::tree::Node;
+
+use super::constants::{CJK_CHARS_PER_MINUTE, WORDS_PER_MINUTE};
+use super::language::is_cjk;
+use super::util::Util;
+
+// Statistics of the extracted article body.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct TextStats {
+    // words separated by whitespace plus every chinese or japanese character
+    pub word_count: usize,
+    // characters of the text with whitespace collapsed
+    pub char_count: usize,
+    pub paragraph_count: usize,
+    pub image_count: usize,
+    pub link_density: f64,
+    pub reading_time: Duration,
+}
+
+impl TextStats {
+    pub fn from_node(node: &Node) -> Self {
+        let text = Util::get_inner_text(node, true);
+        let paragraph_count = Util::get_elements_by_tag_name(node, "p")
+            .iter()
+            .filter(|paragraph| !paragraph.get_content().trim().is_empty())
+            .count();
+
+        Self {
+            paragraph_count,
+            image_count: Util::get_elements_by_tag_name(node, "img").len(),
+            link_density: Util::get_link_density(node),
+            ..Self::from_text(&text)
+        }
+    }
+
+    // Statistics of plain text, without paragraphs, images and links.
+    pub fn from_text(text: &str) -> Self {
+        let (words, cjk_chars) = Self::count_words(text);
+        let minutes =
+            words as f64 / WORDS_PER_MINUTE as f64 + cjk_chars as f64 / CJK_CHARS_PER_MINUTE as f64;
+
+        Self {
+            word_count: words + cjk_chars,
+            char_count: text
+                .split_whitespace()
+                .collect::<Vec<_>>()
+                .join(" ")
+                .chars()
+                .count(),
+            reading_time: Duration::from_secs_f64(minutes * 60.0),
+            ..Self::default()
+        }
+    }
+
+    // Reading time rounded up to whole minutes, at least one for any text.
+    pub fn reading_minutes(&self) -> u64 {
+        let seconds = self.reading_time.as_secs_f64();
+        if seconds > 0.0 {
+            (seconds / 60.0).ceil() as u64
+        } else {
+            0
+        }
+    }
+
+    // Count space separated words and CJK characters separately, as they are
+    // read at different speeds. Tokens without any letter or digit, like a
+    // lone dash, are no words.
+    fn count_words(text: &str) -> (usize, usize) {
+        let mut words = 0;
+        let mut cjk_chars = 0;
+        let mut in_word = false;
+
+        for c in text.chars() {
+            if is_cjk(c) {
+                cjk_chars += 1;
+                in_word = false;
+            } else if c.is_alphanumeric() {
+                if !in_word {
+                    words += 1;
+                    in_word = true;
+                }
+            } else if c.is_whitespace() {
+                in_word = false;
+            }
+        }
+
+        (words, cjk_chars)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TextStats;
+    use crate::readability::{ExtractOptions, Readability};
+
+    #[test]
+    fn count_words() {
+        let stats = TextStats::from_text("The quick brown fox — jumps over the lazy dog.");
+        assert_eq!(stats.word_count, 9);
+        assert_eq!(stats.char_count, 46);
+
+        // every character of chinese and japanese text counts as a word,
+        // embedded latin words still count once
+        let stats = TextStats::from_text("我们使用Rust编写 readability 程序");
+        assert_eq!(stats.word_count, 10);
+        let stats = TextStats::from_text("東京はとても大きい都市です。");
+        assert_eq!(stats.word_count, 13);
+
+        assert_eq!(TextStats::from_text("").reading_minutes(), 0);
+        assert_eq!(TextStats::from_text("one word").reading_minutes(), 1);
+    }
+
+    #[tokio::test]
+    async fn article_stats() {
+        let paragraph = "Lorem ipsum dolor sit amet, consectetur adipiscing elit, sed do eiusmod tempor incididunt ut labore et dolore magna aliqua. ";
+        let story = format!("<p>{paragraph}</p>").repeat(6);
+        let html = format!(
+            r#"<html><body><article>{story}<p>Read <a href="/more">more about it</a>.</p>
+                <img src="https://example.com/photo.jpg" width="600" height="400"></article></body></html>"#
+        );
+
+        let article = Readability::extract_article(&html, None, &ExtractOptions::default())
+            .await
+            .unwrap();
+        let stats = article.stats.expect("stats of the body");
+        assert_eq!(stats.word_count, 6 * 19 + 4);
+        assert_eq!(stats.paragraph_count, 7);
+        assert_eq!(stats.image_count, 1);
+        assert!(stats.link_density > 0.0 && stats.link_density < 0.05);
+        assert_eq!(stats.reading_minutes(), 1);
+    }
+}
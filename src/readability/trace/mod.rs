@@ -0,0 +1,351 @@
+use libxml::tree::{Node, NodeType};
+use serde_json::{json, Map, Value};
+
+use super::constants::{NODE_ID_ATTR, SCORE_ATTR};
+
+// Record of the decisions taken while extracting an article: every node that
+// was removed or converted, the scored candidates and the retry passes.
+// Enable it with `ExtractOptions::builder().trace(true)`. Disabled traces
+// record nothing, scores that are only needed for the trace are not computed
+// then.
+#[derive(Clone, Debug, Default)]
+pub struct Trace {
+    enabled: bool,
+    pub passes: Vec<TracePass>,
+    pub events: Vec<TraceEvent>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TraceAction {
+    Remove,
+    Convert,
+    Keep,
+}
+
+#[derive(Clone, Debug)]
+pub struct TraceEvent {
+    // index into `Trace::passes`, `None` for decisions outside of the scoring
+    // passes, e.g. when the body was selected by a site config
+    pub pass: Option<usize>,
+    pub action: TraceAction,
+    pub rule: &'static str,
+    pub path: String,
+    // id of the node in an annotated view, see `ExtractOptions::annotate`
+    pub node: Option<usize>,
+    pub scores: Vec<(&'static str, f64)>,
+}
+
+// One run of the scoring algorithm with the flags it ran with. Passes after
+// the first are retries because the previous one found too little text.
+#[derive(Clone, Debug)]
+pub struct TracePass {
+    pub strip_unlikely: bool,
+    pub weigh_classes: bool,
+    pub clean_conditionally: bool,
+    pub candidates: Vec<TraceCandidate>,
+    pub top_candidate: Option<TraceCandidate>,
+    pub text_length: usize,
+    // true for the pass whose content ended up in the article
+    pub selected: bool,
+}
+
+#[derive(Clone, Debug)]
+pub struct TraceCandidate {
+    pub path: String,
+    pub node: Option<usize>,
+    pub content_score: f64,
+}
+
+impl Trace {
+    pub fn new(enabled: bool) -> Self {
+        Self {
+            enabled,
+            ..Self::default()
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    pub fn remove(&mut self, node: &Node, rule: &'static str, scores: &[(&'static str, f64)]) {
+        self.record(TraceAction::Remove, node, rule, scores);
+    }
+
+    pub fn convert(&mut self, node: &Node, rule: &'static str, scores: &[(&'static str, f64)]) {
+        self.record(TraceAction::Convert, node, rule, scores);
+    }
+
+    pub fn keep(&mut self, node: &Node, rule: &'static str, scores: &[(&'static str, f64)]) {
+        self.record(TraceAction::Keep, node, rule, scores);
+    }
+
+    fn record(
+        &mut self,
+        action: TraceAction,
+        node: &Node,
+        rule: &'static str,
+        scores: &[(&'static str, f64)],
+    ) {
+        if !self.enabled {
+            return;
+        }
+
+        self.events.push(TraceEvent {
+            pass: self.passes.len().checked_sub(1),
+            action,
+            rule,
+            path: node_path(node),
+            node: node_id(node),
+            scores: scores.to_vec(),
+        });
+    }
+
+    pub(crate) fn begin_pass(
+        &mut self,
+        strip_unlikely: bool,
+        weigh_classes: bool,
+        clean_conditionally: bool,
+    ) {
+        if !self.enabled {
+            return;
+        }
+
+        self.passes.push(TracePass {
+            strip_unlikely,
+            weigh_classes,
+            clean_conditionally,
+            candidates: Vec::new(),
+            top_candidate: None,
+            text_length: 0,
+            selected: false,
+        });
+    }
+
+    pub(crate) fn candidates<'a>(&mut self, candidates: impl Iterator<Item = &'a Node>) {
+        if let Some(pass) = self.passes.last_mut().filter(|_| self.enabled) {
+            pass.candidates = candidates.map(TraceCandidate::new).collect();
+        }
+    }
+
+    pub(crate) fn top_candidate(&mut self, node: &Node) {
+        if let Some(pass) = self.passes.last_mut().filter(|_| self.enabled) {
+            pass.top_candidate = Some(TraceCandidate::new(node));
+        }
+    }
+
+    pub(crate) fn end_pass(&mut self, text_length: usize, selected: bool) {
+        if let Some(pass) = self.passes.last_mut() {
+            pass.text_length = text_length;
+            pass.selected = selected;
+        }
+    }
+
+    // Mark the pass with the most text as selected, which is what the
+    // extraction falls back to when no pass reached the char threshold.
+    pub(crate) fn select_longest_pass(&mut self) {
+        if let Some(pass) = self.passes.iter_mut().max_by_key(|pass| pass.text_length) {
+            pass.selected = true;
+        }
+    }
+
+    // Append the trace of a following page, keeping its pass indices valid.
+    pub(crate) fn append(&mut self, other: Trace) {
+        let offset = self.passes.len();
+        self.passes.extend(other.passes);
+        self.events
+            .extend(other.events.into_iter().map(|event| TraceEvent {
+                pass: event.pass.map(|pass| pass + offset),
+                ..event
+            }));
+    }
+
+    pub fn to_json(&self) -> Value {
+        let passes = self
+            .passes
+            .iter()
+            .map(|pass| {
+                json!({
+                    "strip_unlikely": pass.strip_unlikely,
+                    "weigh_classes": pass.weigh_classes,
+                    "clean_conditionally": pass.clean_conditionally,
+                    "text_length": pass.text_length,
+                    "selected": pass.selected,
+                    "top_candidate": pass.top_candidate.as_ref().map(TraceCandidate::to_json),
+                    "candidates": pass.candidates.iter().map(TraceCandidate::to_json).collect::<Vec<_>>(),
+                })
+            })
+            .collect::<Vec<_>>();
+
+        let events = self
+            .events
+            .iter()
+            .map(|event| {
+                let scores = event
+                    .scores
+                    .iter()
+                    .map(|(name, score)| (name.to_string(), json!(score)))
+                    .collect::<Map<_, _>>();
+                json!({
+                    "pass": event.pass,
+                    "action": match event.action {
+                        TraceAction::Remove => "remove",
+                        TraceAction::Convert => "convert",
+                        TraceAction::Keep => "keep",
+                    },
+                    "rule": event.rule,
+                    "path": event.path,
+                    "scores": scores,
+                })
+            })
+            .collect::<Vec<_>>();
+
+        json!({ "passes": passes, "events": events })
+    }
+
+    pub fn to_json_string(&self) -> String {
+        serde_json::to_string_pretty(&self.to_json()).unwrap_or_default()
+    }
+}
+
+impl TraceCandidate {
+    fn new(node: &Node) -> Self {
+        Self {
+            path: node_path(node),
+            node: node_id(node),
+            content_score: node
+                .get_attribute(SCORE_ATTR)
+                .and_then(|score| score.parse().ok())
+                .unwrap_or(0.0),
+        }
+    }
+
+    fn to_json(&self) -> Value {
+        json!({
+            "path": self.path,
+            "content_score": self.content_score,
+        })
+    }
+}
+
+fn node_id(node: &Node) -> Option<usize> {
+    node.get_attribute(NODE_ID_ATTR)
+        .and_then(|id| id.parse().ok())
+}
+
+// XPath like location of a node at the time of the decision. Elements with an
+// id are addressed by it, everything else by its position among the siblings
+// of the same name.
+fn node_path(node: &Node) -> String {
+    let mut steps = Vec::new();
+    let mut current = Some(node.clone());
+
+    while let Some(node) = current {
+        if node.get_type() == Some(NodeType::DocumentNode)
+            || node.get_type() == Some(NodeType::HtmlDocumentNode)
+        {
+            break;
+        }
+
+        let name = node.get_name().to_lowercase();
+        let step = if node.get_type() == Some(NodeType::TextNode) {
+            "text()".to_owned()
+        } else if let Some(id) = node.get_property("id").filter(|id| !id.is_empty()) {
+            format!("{name}[@id='{id}']")
+        } else {
+            let same_name = |sibling: &Node| {
+                sibling.get_type() == Some(NodeType::ElementNode)
+                    && sibling.get_name().eq_ignore_ascii_case(&name)
+            };
+            let preceding = siblings(&node, Node::get_prev_sibling)
+                .filter(same_name)
+                .count();
+            let following = siblings(&node, Node::get_next_sibling)
+                .filter(same_name)
+                .count();
+            if preceding + following == 0 {
+                name
+            } else {
+                format!("{name}[{}]", preceding + 1)
+            }
+        };
+
+        steps.push(step);
+        current = node.get_parent();
+    }
+
+    steps.reverse();
+    format!("/{}", steps.join("/"))
+}
+
+fn siblings(node: &Node, next: fn(&Node) -> Option<Node>) -> impl Iterator<Item = Node> {
+    std::iter::successors(next(node), next)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::Value;
+
+    use crate::readability::{ExtractOptions, Readability};
+
+    #[tokio::test]
+    async fn trace_decisions() {
+        let paragraph = "Lorem ipsum dolor sit amet, consectetur adipiscing elit, sed do eiusmod tempor incididunt ut labore et dolore magna aliqua. ";
+        let story = format!("<p>{paragraph}</p>").repeat(6);
+        let html = format!(
+            r#"<html><body>
+                <div class="sidebar"><p>{paragraph}</p></div>
+                <div id="story">{story}</div>
+                <aside><p>Related</p></aside>
+                <form><input name="q"></form>
+                <noscript><p>Enable javascript</p></noscript>
+                <div style="display: none"><p>Hidden</p></div>
+                <footer><p>Imprint</p></footer>
+            </body></html>"#
+        );
+
+        let options = ExtractOptions::builder().trace(true).build().unwrap();
+        let article = Readability::extract_article(&html, None, &options)
+            .await
+            .unwrap();
+        let trace = article.trace.expect("trace enabled");
+
+        let json = trace.to_json();
+        let events = json["events"].as_array().unwrap();
+        assert!(events
+            .iter()
+            .any(|event| event["rule"] == "unlikely_candidate"
+                && event["path"] == "/html/body/div[1]"));
+        for (rule, path) in [
+            ("aside", "/html/body/aside"),
+            ("form", "/html/body/form"),
+            ("noscript", "/html/body/noscript"),
+            ("display_none", "/html/body/div[3]"),
+            ("footer", "/html/body/footer"),
+        ] {
+            let removed = |event: &&Value| {
+                event["action"] == "remove" && event["rule"] == rule && event["path"] == path
+            };
+            assert_eq!(events.iter().filter(removed).count(), 1, "{rule}");
+        }
+
+        let passes = json["passes"].as_array().unwrap();
+        assert_eq!(passes.len(), 1);
+        assert_eq!(passes[0]["selected"], true);
+        assert_eq!(
+            passes[0]["top_candidate"]["path"],
+            "/html/body/div[@id='story']"
+        );
+        assert!(
+            passes[0]["candidates"][0]["content_score"]
+                .as_f64()
+                .unwrap()
+                > 0.0
+        );
+
+        let article = Readability::extract_article(&html, None, &ExtractOptions::default())
+            .await
+            .unwrap();
+        assert!(article.trace.is_none());
+    }
+}
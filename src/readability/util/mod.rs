@@ -1,5 +1,6 @@
 use std::collections::HashSet;
 
+use chrono::{DateTime, NaiveDate, NaiveDateTime, TimeZone, Utc};
 use libxml::{
     tree::{Document, Node, NodeType},
     xpath::Context,
@@ -10,71 +11,14 @@ use reqwest::{
 };
 use tokio::fs::DirEntry;
 
-use super::constants;
+use super::{constants, ExtractOptions, Trace};
 
-use thiserror::Error;
+// these used to be defined here, keep the old paths working
+pub use super::config::{ConfigEntry, ConfigError, Header, Replace};
+pub use super::FullTextParserError;
 
 use url::Url;
 
-#[derive(Error, Debug)]
-pub enum ConfigError {
-    #[error("IO error")]
-    IO(#[from] std::io::Error),
-    #[error("Unknown Error")]
-    Unknown,
-}
-
-#[derive(Error, Debug)]
-pub enum FullTextParserError {
-    #[error("libXml Error")]
-    Xml,
-    #[error("No content found")]
-    Scrape,
-    #[error("Url Error")]
-    Url(#[from] url::ParseError),
-    #[error("Http request failed")]
-    Http,
-    #[error("Config Error")]
-    Config,
-    #[error("IO Error")]
-    IO,
-    #[error("Content-type suggest no html")]
-    ContentType,
-    #[error("Invalid UTF8 Text")]
-    Utf8(#[from] std::str::Utf8Error),
-    #[error("Readability Error")]
-    Readability,
-    #[error("Unknown Error")]
-    Unknown,
-}
-
-#[derive(Clone, Debug)]
-pub struct Replace {
-    pub to_replace: String,
-    pub replace_with: String,
-}
-
-#[derive(Clone, Debug)]
-pub struct Header {
-    pub name: String,
-    pub value: String,
-}
-
-#[derive(Clone, Debug, Default)]
-pub struct ConfigEntry {
-    pub xpath_title: Vec<String>,
-    pub xpath_author: Vec<String>,
-    pub xpath_date: Vec<String>,
-    pub xpath_body: Vec<String>,
-    pub xpath_strip: Vec<String>,
-    pub strip_id_or_class: Vec<String>,
-    pub strip_image_src: Vec<String>,
-    pub replace: Vec<Replace>,
-    pub header: Vec<Header>,
-    pub single_page_link: Option<String>,
-    pub next_page_link: Option<String>,
-}
-
 pub struct Util;
 
 impl Util {
@@ -116,19 +60,11 @@ impl Util {
     ) -> Result<HeaderMap, FullTextParserError> {
         let mut headers = HeaderMap::new();
 
-        if let Some(config) = site_specific_rule {
-            for header in &config.header {
-                let name = HeaderName::from_bytes(header.name.as_bytes())
-                    .map_err(|_| FullTextParserError::Config)?;
-                let value = header
-                    .value
-                    .parse::<HeaderValue>()
-                    .map_err(|_| FullTextParserError::Config)?;
-                headers.insert(name, value);
-            }
-        }
-
-        for header in &global_rule.header {
+        // site headers come last so they override global ones of the same name
+        let site_headers = site_specific_rule
+            .map(|config| config.header.as_slice())
+            .unwrap_or_default();
+        for header in global_rule.header.iter().chain(site_headers) {
             let name = HeaderName::from_bytes(header.name.as_bytes())
                 .map_err(|_| FullTextParserError::Config)?;
             let value = header
@@ -141,20 +77,27 @@ impl Util {
         Ok(headers)
     }
 
-    pub fn find_page_url(xpath_ctx: &Context, xpath_page_link: &str) -> Option<url::Url> {
+    // Resolve the URL a `single_page_link` or `next_page_link` XPath points to.
+    // The XPath may select the link element itself or its `href` attribute,
+    // relative links are joined with the URL of the current page.
+    pub fn find_page_url(
+        xpath_ctx: &Context,
+        xpath_page_link: &str,
+        base_url: &url::Url,
+    ) -> Option<url::Url> {
         let res = Self::evaluate_xpath(xpath_ctx, xpath_page_link, false).ok()?;
         let mut url = None;
 
         for node in res {
-            let content = node.get_content();
-            let url_str = if content.trim().is_empty() && node.get_attribute("href").is_some() {
-                node.get_attribute("href")
-                    .expect("already checked for href")
-            } else {
-                content
-            };
+            let url_str = node
+                .get_attribute("href")
+                .unwrap_or_else(|| node.get_content());
+            let url_str = url_str.trim();
+            if url_str.is_empty() || url_str.starts_with('#') {
+                continue;
+            }
 
-            if let Ok(parsed_url) = url::Url::parse(&url_str) {
+            if let Ok(parsed_url) = base_url.join(url_str) {
                 url = Some(parsed_url);
                 break;
             }
@@ -227,7 +170,7 @@ impl Util {
 
     pub fn extract_value(context: &Context, xpath: &str) -> Result<String, FullTextParserError> {
         let node_vec = Util::evaluate_xpath(context, xpath, false)?;
-        if let Some(val) = node_vec.get(0) {
+        if let Some(val) = node_vec.first() {
             return Ok(val.get_content());
         }
 
@@ -253,7 +196,12 @@ impl Util {
         Ok(val.trim().to_string())
     }
 
-    pub fn strip_node(context: &Context, xpath: &str) -> Result<(), FullTextParserError> {
+    pub fn strip_node(
+        context: &Context,
+        xpath: &str,
+        rule: &'static str,
+        trace: &mut Trace,
+    ) -> Result<(), FullTextParserError> {
         let node_vec = Util::evaluate_xpath(context, xpath, false)?;
         let node_vec_clone = node_vec.clone();
 
@@ -272,6 +220,7 @@ impl Util {
                 continue;
             }
 
+            trace.remove(&node, rule, &[]);
             node.unlink();
         }
         Ok(())
@@ -294,6 +243,7 @@ impl Util {
     pub fn strip_id_or_class(
         context: &Context,
         id_or_class: &str,
+        trace: &mut Trace,
     ) -> Result<(), FullTextParserError> {
         let xpath = &format!(
             "//*[contains(@class, '{}') or contains(@id, '{}')]",
@@ -308,6 +258,7 @@ impl Util {
         let query = &format!("{}[not(ancestor::{})]", xpath, ancestor);
         let node_vec = Util::evaluate_xpath(context, query, false)?;
         for mut node in node_vec {
+            trace.remove(&node, "strip_id_or_class", &[]);
             node.unlink();
         }
         Ok(())
@@ -393,6 +344,84 @@ impl Util {
         }
     }
 
+    // Parse machine readable (RFC 3339, RFC 2822, ISO 8601 without offset) as well as
+    // common textual dates ("May 4, 2023", "4th May 2023") found in bylines.
+    // Dates without timezone information are assumed to be UTC.
+    pub fn parse_date(date: &str) -> Option<DateTime<Utc>> {
+        let date = date.trim();
+        if date.is_empty() {
+            return None;
+        }
+
+        if let Ok(date) = DateTime::parse_from_rfc3339(date) {
+            return Some(date.with_timezone(&Utc));
+        }
+
+        if let Ok(date) = DateTime::parse_from_rfc2822(date) {
+            return Some(date.with_timezone(&Utc));
+        }
+
+        for format in [
+            "%Y-%m-%dT%H:%M:%S%.f%z",
+            "%Y-%m-%dT%H:%M%z",
+            "%Y-%m-%d %H:%M:%S%.f%z",
+        ] {
+            if let Ok(date) = DateTime::parse_from_str(date, format) {
+                return Some(date.with_timezone(&Utc));
+            }
+        }
+
+        for format in [
+            "%Y-%m-%dT%H:%M:%S%.f",
+            "%Y-%m-%dT%H:%M",
+            "%Y-%m-%d %H:%M:%S%.f",
+        ] {
+            if let Ok(date) = NaiveDateTime::parse_from_str(date, format) {
+                return Some(Utc.from_utc_datetime(&date));
+            }
+        }
+
+        let text = constants::DATE_TEXT.find(date)?.as_str();
+        let text = constants::DATE_ORDINAL.replace_all(text, "$1");
+        let text = constants::NORMALIZE
+            .replace_all(&text, " ")
+            .replace("Sept ", "Sep ");
+
+        ["%Y-%m-%d", "%B %d %Y", "%d %B %Y"]
+            .iter()
+            .find_map(|format| NaiveDate::parse_from_str(&text, format).ok())
+            .and_then(|date| date.and_hms_opt(0, 0, 0))
+            .map(|date| Utc.from_utc_datetime(&date))
+    }
+
+    // Split a byline like "By Jane Doe and John Roe" into the individual names.
+    // Fragments containing digits are dropped since they usually are dates or times.
+    pub fn split_byline(byline: &str) -> Vec<String> {
+        let byline = escaper::decode_html(byline).unwrap_or_else(|_| byline.into());
+        let byline = constants::NORMALIZE.replace_all(byline.trim(), " ");
+        let mut authors: Vec<String> = Vec::new();
+
+        for part in constants::BYLINE_SEPARATOR.split(&byline) {
+            let name = constants::BYLINE_PREFIX.replace(part, "");
+            let name = name
+                .trim_matches(|c: char| c.is_whitespace() || c.is_ascii_punctuation())
+                .to_string();
+
+            if name.is_empty() || name.len() >= 100 || name.chars().any(|c| c.is_numeric()) {
+                continue;
+            }
+
+            if !authors
+                .iter()
+                .any(|a| a.to_lowercase() == name.to_lowercase())
+            {
+                authors.push(name);
+            }
+        }
+
+        authors
+    }
+
     pub fn text_similarity(a: &str, b: &str) -> f64 {
         let a = a.to_lowercase();
         let b = b.to_lowercase();
@@ -644,17 +673,19 @@ impl Util {
         }
     }
 
-    pub fn clean_headers(root: &mut Node) {
+    pub fn clean_headers(root: &mut Node, options: &ExtractOptions, trace: &mut Trace) {
         let mut nodes = Util::get_elements_by_tag_name(root, "h1");
         nodes.append(&mut Util::get_elements_by_tag_name(root, "h2"));
 
         for mut node in nodes.into_iter().rev() {
-            if Util::get_class_weight(&node) < 0 {
+            let weight = Util::get_class_weight(&node, options);
+            if weight < 0 {
                 log::debug!(
                     "Removing header with low class weight: {} {}",
                     node.get_name(),
                     node.get_attribute("class").unwrap_or_default()
                 );
+                trace.remove(&node, "negative_header", &[("class_weight", weight as f64)]);
                 node.unlink();
             }
         }
@@ -674,7 +705,12 @@ impl Util {
 
     // Clean an element of all tags of type "tag" if they look fishy.
     // "Fishy" is an algorithm based on content length, classnames, link density, number of images & embeds, etc.
-    pub fn clean_conditionally(root: &mut Node, tag: &str) {
+    pub fn clean_conditionally(
+        root: &mut Node,
+        tag: &str,
+        options: &ExtractOptions,
+        trace: &mut Trace,
+    ) {
         // Gather counts for other typical elements embedded within.
         // Traverse backwards so we can remove nodes at the same time
         // without effecting the traversal.
@@ -683,13 +719,24 @@ impl Util {
         let nodes = Util::get_elements_by_tag_name(root, tag);
 
         for mut node in nodes.into_iter().rev() {
-            if Self::should_remove(&node, tag) {
+            if let Some((rule, scores)) =
+                Self::should_remove(&node, tag, options, trace.is_enabled())
+            {
+                trace.remove(&node, rule, &scores);
                 node.unlink();
             }
         }
     }
 
-    fn should_remove(node: &Node, tag: &str) -> bool {
+    // Returns the name of the rule that makes `node` look fishy together with
+    // the scores it was based on, or `None` if the node should be kept. The
+    // scores are only collected with `with_scores`.
+    fn should_remove(
+        node: &Node,
+        tag: &str,
+        options: &ExtractOptions,
+        with_scores: bool,
+    ) -> Option<(&'static str, Vec<(&'static str, f64)>)> {
         // First check if this node IS data table, in which case don't remove it.
         let mut is_list = tag == "ul" || tag == "ol";
         if !is_list {
@@ -706,21 +753,26 @@ impl Util {
         }
 
         if tag == "table" && Self::is_data_table(node) {
-            return false;
+            return None;
         }
 
         // Next check if we're inside a data table, in which case don't remove it as well.
         if Self::has_ancestor_tag(node, "table", Some(u64::MAX), Some(Self::is_data_table)) {
-            return false;
+            return None;
         }
 
         if Self::has_ancestor_tag(node, "code", None, None::<fn(&Node) -> bool>) {
-            return false;
+            return None;
         }
 
-        let weight = Self::get_class_weight(node);
+        let weight = Self::get_class_weight(node, options);
         if weight < 0 {
-            return true;
+            let scores = if with_scores {
+                vec![("class_weight", weight as f64)]
+            } else {
+                Vec::new()
+            };
+            return Some(("negative_class_weight", scores));
         }
 
         if Self::get_char_count(node, ',') < 10 {
@@ -729,7 +781,7 @@ impl Util {
             // ominous signs, remove the element.
             let p = Self::get_elements_by_tag_name(node, "p").len();
             let img = Self::get_elements_by_tag_name(node, "img").len();
-            let li = Self::get_elements_by_tag_name(node, "li").len() as i64 - 100;
+            let li = Self::get_elements_by_tag_name(node, "li").len();
             let input = Self::get_elements_by_tag_name(node, "input").len();
             let heading_density =
                 Self::get_text_density(node, &["h1", "h2", "h3", "h4", "h5", "h6"]);
@@ -742,13 +794,13 @@ impl Util {
                     // If this embed has attribute that matches video regex, don't delete it.
                     for (_name, value) in embed_node.get_attributes() {
                         if constants::VIDEOS.is_match(&value) {
-                            return false;
+                            return None;
                         }
                     }
 
                     // For embed with <object> tag, check inner HTML as well.
                     // if embed_node.get_name().to_lowercase() == "object" && constants::VIDEOS.is_match(embed_node.innerHTML) {
-                    //     return false;
+                    //     return None;
                     // }
 
                     embed_count += 1;
@@ -765,23 +817,55 @@ impl Util {
             let video_obj_count = Util::get_elements_by_tag_name(node, "videoobject").len();
 
             if image_obj_count > 0 || video_obj_count > 0 {
-                return false;
+                return None;
             }
 
-            let have_to_remove = (img > 1 && (p as f64 / img as f64) < 0.5 && !has_figure_ancestor)
-                || (!is_list && li > p as i64)
-                || (input as f64 > f64::floor(p as f64 / 3.0))
-                || (!is_list
-                    && heading_density < 0.9
-                    && content_length < 25
-                    && (img == 0 || img > 2)
-                    && !has_figure_ancestor)
-                || (!is_list && weight < 25 && link_density > 0.2)
-                || (weight >= 25 && link_density > 0.5)
-                || ((embed_count == 1 && content_length < 75) || embed_count > 1);
+            let rule = if img > 1 && (p as f64 / img as f64) < 0.5 && !has_figure_ancestor {
+                Some("too_many_images")
+            } else if !is_list && li > p + 100 {
+                Some("too_many_list_items")
+            } else if input as f64 > f64::floor(p as f64 / 3.0) {
+                Some("too_many_inputs")
+            } else if !is_list
+                && heading_density < 0.9
+                && content_length < 25
+                && (img == 0 || img > 2)
+                && !has_figure_ancestor
+            {
+                Some("too_little_content")
+            } else if !is_list
+                && weight < options.class_weight
+                && link_density > options.link_density
+            {
+                Some("link_density")
+            } else if weight >= options.class_weight && link_density > options.weighted_link_density
+            {
+                Some("weighted_link_density")
+            } else if (embed_count == 1 && content_length < 75) || embed_count > 1 {
+                Some("too_many_embeds")
+            } else {
+                None
+            };
+            let have_to_remove = rule.map(|rule| {
+                if !with_scores {
+                    return (rule, Vec::new());
+                }
+                let scores = vec![
+                    ("class_weight", weight as f64),
+                    ("link_density", link_density),
+                    ("content_length", content_length as f64),
+                    ("paragraphs", p as f64),
+                    ("images", img as f64),
+                    ("list_items", li as f64),
+                    ("inputs", input as f64),
+                    ("embeds", embed_count as f64),
+                    ("heading_density", heading_density),
+                ];
+                (rule, scores)
+            });
 
             // Allow simple lists of images to remain in pages
-            if is_list && have_to_remove {
+            if is_list && have_to_remove.is_some() {
                 for child in node.get_child_elements() {
                     // Don't filter in lists with li's that contain more than one child
                     if child.get_child_elements().len() > 1 {
@@ -789,45 +873,20 @@ impl Util {
                     }
                 }
 
-                let li_count = Util::get_elements_by_tag_name(node, "li").len();
                 // Only allow the list to remain if every li contains an image
-                if img == li_count {
-                    return false;
+                if img == li {
+                    return None;
                 }
             }
 
             have_to_remove
         } else {
-            false
+            None
         }
     }
 
-    pub fn get_class_weight(node: &Node) -> i64 {
-        let mut weight = 0;
-
-        // Look for a special classname
-        if let Some(class_names) = node.get_property("class") {
-            if constants::NEGATIVE.is_match(&class_names) {
-                weight -= 25;
-            }
-
-            if constants::POSITIVE.is_match(&class_names) {
-                weight += 25;
-            }
-        }
-
-        // Look for a special ID
-        if let Some(class_names) = node.get_property("id") {
-            if constants::NEGATIVE.is_match(&class_names) {
-                weight -= 25;
-            }
-
-            if constants::POSITIVE.is_match(&class_names) {
-                weight += 25;
-            }
-        }
-
-        weight
+    pub fn get_class_weight(node: &Node, options: &ExtractOptions) -> i64 {
+        options.class_weight(node)
     }
 
     fn get_char_count(node: &Node, char: char) -> usize {
@@ -1107,6 +1166,7 @@ impl Util {
 
 #[cfg(test)]
 mod tests {
+    use chrono::{TimeZone, Utc};
     use libxml::parser::Parser;
 
     use super::Util;
@@ -1127,6 +1187,53 @@ mod tests {
         assert_eq!(expected, result);
     }
 
+    #[test]
+    fn split_byline() {
+        assert_eq!(
+            Util::split_byline("By Jane Doe and John Roe"),
+            vec!["Jane Doe", "John Roe"]
+        );
+        assert_eq!(
+            Util::split_byline(
+                "  written by: Jane Doe, John Roe &amp; Max Mustermann | May 4, 2023"
+            ),
+            vec!["Jane Doe", "John Roe", "Max Mustermann"]
+        );
+        assert_eq!(Util::split_byline("Jane Doe"), vec!["Jane Doe"]);
+        assert!(Util::split_byline("By").is_empty());
+    }
+
+    #[test]
+    fn parse_date() {
+        let expected = Utc.with_ymd_and_hms(2023, 5, 4, 8, 0, 0).unwrap();
+        assert_eq!(
+            Util::parse_date("2023-05-04T10:00:00+02:00"),
+            Some(expected)
+        );
+        assert_eq!(Util::parse_date("2023-05-04T08:00:00Z"), Some(expected));
+        assert_eq!(
+            Util::parse_date("2023-05-04T08:00:00.000+0000"),
+            Some(expected)
+        );
+        assert_eq!(
+            Util::parse_date("Thu, 04 May 2023 08:00:00 GMT"),
+            Some(expected)
+        );
+
+        let expected = Utc.with_ymd_and_hms(2023, 5, 4, 0, 0, 0).unwrap();
+        assert_eq!(Util::parse_date("2023-05-04"), Some(expected));
+        assert_eq!(
+            Util::parse_date("By Jane Doe | May 4, 2023"),
+            Some(expected)
+        );
+        assert_eq!(Util::parse_date("Published 4th May 2023"), Some(expected));
+        assert_eq!(
+            Util::parse_date("Sept. 14, 2022").map(|d| d.to_rfc3339()),
+            Some("2022-09-14T00:00:00+00:00".into())
+        );
+        assert_eq!(Util::parse_date("Jane Doe"), None);
+    }
+
     #[test]
     fn replace_brs_1() {
         replace_brs(
@@ -0,0 +1,259 @@
+use std::fmt;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use headless_chrome::browser::tab::Tab;
+use serde_json::Value;
+use thiserror::Error;
+
+use super::ConfigEntry;
+
+// how often a condition is checked while waiting
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+const RESOURCE_BUFFER_SCRIPT: &str = "performance.setResourceTimingBufferSize(1000000)";
+
+#[derive(Error, Debug)]
+pub enum WaitError {
+    #[error("Timed out after {timeout:?} waiting for {condition}")]
+    Timeout {
+        condition: WaitCondition,
+        timeout: Duration,
+    },
+    #[error("Checking {condition} failed: {source}")]
+    Browser {
+        condition: WaitCondition,
+        source: anyhow::Error,
+    },
+}
+
+// What a page has to reach after navigation before it is captured. Single
+// page apps are only a shell when the navigation finishes.
+#[derive(Clone, Debug, PartialEq)]
+pub enum WaitCondition {
+    // an element matching the CSS selector exists
+    Selector(String),
+    // an element matching the XPath exists
+    XPath(String),
+    // the document is loaded and no further resource finished loading for
+    // this long
+    NetworkIdle(Duration),
+    // just wait this long
+    Delay(Duration),
+    // the length of the body text didn't change for this long
+    TextStable(Duration),
+}
+
+impl fmt::Display for WaitCondition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Selector(selector) => write!(f, "selector '{selector}'"),
+            Self::XPath(xpath) => write!(f, "xpath '{xpath}'"),
+            Self::NetworkIdle(idle) => write!(f, "network idle for {idle:?}"),
+            Self::Delay(delay) => write!(f, "delay of {delay:?}"),
+            Self::TextStable(stable) => write!(f, "text stable for {stable:?}"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Wait {
+    pub condition: WaitCondition,
+    pub timeout: Duration,
+}
+
+impl Wait {
+    pub fn new(condition: WaitCondition, timeout: Duration) -> Self {
+        Self { condition, timeout }
+    }
+
+    // Wait for the elements named by the `wait_for:` XPaths of a site config,
+    // or for its `body:` XPaths if it has none.
+    pub fn for_config(config: &ConfigEntry, timeout: Duration) -> Option<Self> {
+        let xpaths = if config.xpath_wait.is_empty() {
+            &config.xpath_body
+        } else {
+            &config.xpath_wait
+        };
+
+        (!xpaths.is_empty()).then(|| Self::new(WaitCondition::XPath(xpaths.join(" | ")), timeout))
+    }
+
+    pub(crate) fn wait(&self, tab: &Tab) -> Result<(), WaitError> {
+        match &self.condition {
+            WaitCondition::Selector(selector) => {
+                let expression =
+                    format!("document.querySelector({}) !== null", js_string(selector));
+                self.poll(|| Ok(self.evaluate(tab, &expression)? == Value::Bool(true)))
+            }
+            WaitCondition::XPath(xpath) => {
+                let expression = format!(
+                    "document.evaluate({}, document, null, XPathResult.FIRST_ORDERED_NODE_TYPE, null).singleNodeValue !== null",
+                    js_string(xpath)
+                );
+                self.poll(|| Ok(self.evaluate(tab, &expression)? == Value::Bool(true)))
+            }
+            WaitCondition::NetworkIdle(idle) => {
+                // resources still loading can't be observed from the page, the
+                // count of finished ones stops growing once the page is idle.
+                // Browsers only keep 250 entries by default, lift that limit
+                // so the count keeps growing on heavy pages.
+                self.evaluate(tab, RESOURCE_BUFFER_SCRIPT)?;
+                let expression = "document.readyState === 'complete' ? performance.getEntriesByType('resource').length : -1";
+                self.poll_stable(tab, expression, *idle)
+            }
+            WaitCondition::Delay(delay) => {
+                thread::sleep(*delay);
+                Ok(())
+            }
+            WaitCondition::TextStable(stable) => {
+                let expression = "document.body && document.body.innerText.length > 0 ? document.body.innerText.length : -1";
+                self.poll_stable(tab, expression, *stable)
+            }
+        }
+    }
+
+    fn poll(&self, mut check: impl FnMut() -> Result<bool, WaitError>) -> Result<(), WaitError> {
+        let start = Instant::now();
+        loop {
+            if check()? {
+                return Ok(());
+            }
+            if start.elapsed() >= self.timeout {
+                return Err(WaitError::Timeout {
+                    condition: self.condition.clone(),
+                    timeout: self.timeout,
+                });
+            }
+            thread::sleep(POLL_INTERVAL);
+        }
+    }
+
+    // Wait until `expression` evaluates to the same non negative number for
+    // `duration`.
+    fn poll_stable(
+        &self,
+        tab: &Tab,
+        expression: &str,
+        duration: Duration,
+    ) -> Result<(), WaitError> {
+        let mut last: Option<(Value, Instant)> = None;
+        self.poll(|| {
+            let value = self.evaluate(tab, expression)?;
+            if value.as_i64().unwrap_or(-1) < 0 {
+                last = None;
+                return Ok(false);
+            }
+
+            match &last {
+                Some((last_value, since)) if *last_value == value => {
+                    Ok(since.elapsed() >= duration)
+                }
+                _ => {
+                    last = Some((value, Instant::now()));
+                    Ok(false)
+                }
+            }
+        })
+    }
+
+    fn evaluate(&self, tab: &Tab, expression: &str) -> Result<Value, WaitError> {
+        tab.evaluate(expression, false)
+            .map(|object| object.value.unwrap_or(Value::Null))
+            .map_err(|source| WaitError::Browser {
+                condition: self.condition.clone(),
+                source,
+            })
+    }
+}
+
+// quote `value` as a javascript string literal
+fn js_string(value: &str) -> String {
+    Value::String(value.to_owned()).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+    use std::time::Duration;
+
+    use url::Url;
+
+    use super::{js_string, Wait, WaitCondition, WaitError};
+    use crate::readability::{BrowserSession, ConfigEntry, RenderOptions, SessionOptions};
+
+    #[test]
+    fn wait_for_config() {
+        let timeout = Duration::from_secs(5);
+        let mut config = ConfigEntry::default();
+        assert_eq!(Wait::for_config(&config, timeout), None);
+
+        config.xpath_body = vec!["//article".into(), "//main".into()];
+        let wait = Wait::for_config(&config, timeout).unwrap();
+        assert_eq!(
+            wait.condition,
+            WaitCondition::XPath("//article | //main".into())
+        );
+
+        config.xpath_wait = vec!["//article/p".into()];
+        let wait = Wait::for_config(&config, timeout).unwrap();
+        assert_eq!(wait.condition, WaitCondition::XPath("//article/p".into()));
+
+        let error = WaitError::Timeout {
+            condition: WaitCondition::Selector("#app .story".into()),
+            timeout,
+        };
+        assert_eq!(
+            error.to_string(),
+            "Timed out after 5s waiting for selector '#app .story'"
+        );
+        assert_eq!(js_string(r#"a[href="x"]"#), r#""a[href=\"x\"]""#);
+    }
+
+    // needs Chrome, run with `cargo test -- --ignored`
+    #[test]
+    #[ignore]
+    fn wait_in_browser() {
+        let session = BrowserSession::new(SessionOptions::default()).unwrap();
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("resources/tests/wait/delayed.html");
+        let url = Url::from_file_path(path).unwrap();
+        let timeout = Duration::from_secs(10);
+        let html = |conditions: Vec<WaitCondition>| {
+            let options = RenderOptions {
+                wait: conditions
+                    .into_iter()
+                    .map(|condition| Wait::new(condition, timeout))
+                    .collect(),
+                ..RenderOptions::default()
+            };
+            session.html(url.as_str(), &options)
+        };
+
+        // the page adds an update every 300ms, five in total
+        let html_now = html(vec![]).unwrap();
+        assert!(!html_now.contains("Update 5"));
+
+        for condition in [
+            WaitCondition::Selector("#last".into()),
+            WaitCondition::XPath("//p[@id='last']".into()),
+            WaitCondition::TextStable(Duration::from_secs(1)),
+            WaitCondition::Delay(Duration::from_secs(2)),
+        ] {
+            let html = html(vec![condition.clone()]).unwrap();
+            assert!(html.contains("Update 5"), "{condition} returned early");
+        }
+        assert!(html(vec![WaitCondition::NetworkIdle(Duration::from_millis(500))]).is_ok());
+
+        let options = RenderOptions {
+            wait: vec![Wait::new(
+                WaitCondition::Selector("#never".into()),
+                Duration::from_millis(500),
+            )],
+            ..RenderOptions::default()
+        };
+        let error = session.html(url.as_str(), &options).unwrap_err();
+        assert!(matches!(
+            error.downcast_ref::<WaitError>(),
+            Some(WaitError::Timeout { .. })
+        ));
+    }
+}